@@ -1,13 +1,18 @@
 // Copyright 2024 ADM Contributors
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::path::PathBuf;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+use std::time::Duration;
 
 use anyhow::anyhow;
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use fendermint_actor_machine::WriteAccess;
+use fendermint_actor_objectstore::ObjectList;
 use fendermint_crypto::SecretKey;
 use fendermint_vm_message::query::FvmQueryHeight;
+use futures::StreamExt;
 use fvm_shared::address::Address;
 use serde_json::{json, Value};
 use tendermint_rpc::Url;
@@ -17,12 +22,17 @@ use std::collections::HashMap;
 
 use adm_provider::{
     json_rpc::JsonRpcProvider,
+    response::Cid,
     util::{parse_address, parse_query_height, parse_metadata},
 };
-use adm_sdk::machine::objectstore::{AddOptions, DeleteOptions, GetOptions};
+use adm_sdk::machine::objectstore::{
+    compute_cid, AddOptions, Compression as SDKCompression, DeleteOptions, DeletePrefixOptions,
+    DownloadOptions, EncryptionKey, ExportOptions, GetOptions, ImportOptions, PresignOptions,
+    PresignedUpload, SyncDownOptions, SyncOptions,
+};
 use adm_sdk::{
     machine::{
-        objectstore::{ObjectStore, QueryOptions},
+        objectstore::{ObjectStore, QueryItem, QueryOptions},
         Machine,
     },
     TxParams,
@@ -30,9 +40,31 @@ use adm_sdk::{
 use adm_signer::{key::parse_secret_key, AccountKind, Void, Wallet};
 
 use crate::{
-    get_address, get_rpc_url, get_subnet_id, print_json, AddressArgs, BroadcastMode, Cli, TxArgs,
+    get_address, get_progress, get_rpc_url, get_subnet_id, print_json, AddressArgs, BroadcastMode,
+    Cli, TxArgs,
 };
 
+#[cfg(target_os = "linux")]
+mod mount;
+mod serve;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum Compression {
+    /// Gzip compression.
+    Gzip,
+    /// Zstandard compression.
+    Zstd,
+}
+
+impl Compression {
+    pub fn get(&self) -> SDKCompression {
+        match self {
+            Compression::Gzip => SDKCompression::Gzip,
+            Compression::Zstd => SDKCompression::Zstd,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Args)]
 pub struct ObjectstoreArgs {
     #[command(subcommand)]
@@ -52,8 +84,37 @@ enum ObjectstoreCommands {
     Delete(ObjectstoreDeleteArgs),
     /// Get an object.
     Get(ObjectstoreGetArgs),
+    /// Get an object's info without downloading it.
+    Stat(ObjectstoreStatArgs),
+    /// Compute the CID of a local file as `add` would, without uploading it.
+    Cid(ObjectstoreCidArgs),
+    /// Verify a local file against the CID of a stored object.
+    Verify(ObjectstoreVerifyArgs),
+    /// List the versions of an object between two block heights.
+    History(ObjectstoreHistoryArgs),
+    /// Compare the objects in the store between two block heights.
+    Diff(ObjectstoreDiffArgs),
     /// Query for objects.
     Query(ObjectstoreQueryArgs),
+    /// Sync a local directory into the object store.
+    Sync(ObjectstoreSyncArgs),
+    /// Mirror an object store prefix down to a local directory.
+    SyncDown(ObjectstoreSyncDownArgs),
+    /// Export objects to a CAR archive.
+    Export(ObjectstoreExportArgs),
+    /// Import objects from a CAR archive.
+    Import(ObjectstoreImportArgs),
+    /// Serve objects under a prefix over HTTP, e.g., as a static website.
+    Serve(ObjectstoreServeArgs),
+    /// Sign an upload for a key and CID that another party can stream without the private key.
+    Presign(ObjectstorePresignArgs),
+    /// Stage an object's content with a pre-signed upload.
+    UploadPresigned(ObjectstoreUploadPresignedArgs),
+    /// Add a pre-signed object once its content has been staged.
+    AddPresigned(ObjectstoreAddPresignedArgs),
+    /// Mount the object store as a read-only filesystem (Linux only).
+    #[cfg(target_os = "linux")]
+    Mount(ObjectstoreMountArgs),
 }
 
 #[derive(Clone, Debug, Args)]
@@ -85,8 +146,34 @@ struct ObjectstorePutArgs {
     /// Overwrite the object if it already exists.
     #[arg(short, long)]
     overwrite: bool,
+    /// Skip the upload and transaction if the key already holds the same object.
+    #[arg(long)]
+    skip_unchanged: bool,
+    /// Only add the object if the key currently holds an object with this CID.
+    /// The check is made before broadcasting and is not atomic with the transaction.
+    #[arg(long)]
+    if_match: Option<Cid>,
+    /// Journal the add next to the input file in `<INPUT>.adm-journal`, so a retry skips
+    /// hashing and an upload the Object API already acknowledged.
+    /// Uploads are not resumed part-way: one that was cut off is sent again in full, and an
+    /// input that changed starts over.
+    #[arg(long)]
+    journal: bool,
+    /// Wait for the object to be resolved by validators before exiting.
+    #[arg(long)]
+    wait_resolved: bool,
+    /// Maximum time to wait for the object to be resolved.
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "5m", requires = "wait_resolved")]
+    resolve_timeout: Duration,
+    /// Encrypt the object on the client with the key in this file.
+    /// The file must contain 32 raw bytes or 64 hex characters.
+    #[arg(long, conflicts_with = "journal")]
+    encryption_key_file: Option<PathBuf>,
+    /// Compress the object before it's uploaded.
+    #[arg(long, value_enum)]
+    compress: Option<Compression>,
     /// Input file (or stdin) containing the object to upload.
-    //#[clap(default_value = "-")]
+    #[clap(default_value = "-")]
     input: PathBuf,
     /// Broadcast mode for the transaction.
     #[arg(short, long, value_enum, env, default_value_t = BroadcastMode::Commit)]
@@ -106,7 +193,21 @@ struct ObjectstoreDeleteArgs {
     #[arg(short, long, value_parser = parse_address)]
     address: Address,
     /// Key of the object to delete.
-    key: String,
+    #[arg(required_unless_present = "prefix")]
+    key: Option<String>,
+    /// Only delete the object if the key currently holds an object with this CID.
+    /// The check is made before broadcasting and is not atomic with the transaction.
+    #[arg(long)]
+    if_match: Option<Cid>,
+    /// Delete every object under this key prefix (requires `--recursive`).
+    #[arg(long, requires = "recursive", conflicts_with_all = ["key", "if_match"])]
+    prefix: Option<String>,
+    /// Confirm deleting every object under `--prefix`.
+    #[arg(short, long, requires = "prefix")]
+    recursive: bool,
+    /// Only list the objects under `--prefix` that would be deleted.
+    #[arg(long, requires = "prefix")]
+    dry_run: bool,
     /// Broadcast mode for the transaction.
     #[arg(short, long, value_enum, env, default_value_t = BroadcastMode::Commit)]
     broadcast_mode: BroadcastMode,
@@ -143,6 +244,42 @@ struct ObjectstoreGetArgs {
     /// Example: "0-99" (first 100 bytes).
     #[arg(short, long)]
     range: Option<String>,
+    /// Skip verifying the downloaded bytes against the object's CID.
+    #[arg(long)]
+    no_verify: bool,
+    /// Number of concurrent range requests used to download the object.
+    #[arg(long, default_value_t = 1)]
+    parallel: usize,
+    /// Decrypt an encrypted object with the key in this file.
+    /// The file must contain 32 raw bytes or 64 hex characters.
+    #[arg(long, conflicts_with = "range")]
+    encryption_key_file: Option<PathBuf>,
+    /// Write the bytes as stored, without decompressing or decrypting them.
+    #[arg(long, conflicts_with = "encryption_key_file")]
+    raw: bool,
+    /// Write the object to a file instead of stdout.
+    /// Interrupted downloads are resumed from the partial file at the same block height.
+    #[arg(short, long, conflicts_with_all = ["range", "parallel", "no_verify", "encryption_key_file", "raw"])]
+    output: Option<PathBuf>,
+    /// Query block height.
+    /// Possible values:
+    /// "committed" (latest committed block),
+    /// "pending" (consider pending state changes),
+    /// or a specific block height, e.g., "123".
+    #[arg(long, value_parser = parse_query_height, default_value = "committed")]
+    height: FvmQueryHeight,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreStatArgs {
+    /// Node Object API URL.
+    #[arg(long, env)]
+    object_api_url: Option<Url>,
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// Key of the object to inspect.
+    key: String,
     /// Query block height.
     /// Possible values:
     /// "committed" (latest committed block),
@@ -152,6 +289,68 @@ struct ObjectstoreGetArgs {
     height: FvmQueryHeight,
 }
 
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreCidArgs {
+    /// Input file (or stdin) to compute the CID of.
+    #[clap(default_value = "-")]
+    input: PathBuf,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreVerifyArgs {
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// Key of the object to verify against.
+    #[arg(short, long)]
+    key: String,
+    /// Local file (or stdin) to verify.
+    #[clap(default_value = "-")]
+    input: PathBuf,
+    /// Query block height.
+    /// Possible values:
+    /// "committed" (latest committed block),
+    /// "pending" (consider pending state changes),
+    /// or a specific block height, e.g., "123".
+    #[arg(long, value_parser = parse_query_height, default_value = "committed")]
+    height: FvmQueryHeight,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreHistoryArgs {
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// Key of the object.
+    key: String,
+    /// Block height to start searching from.
+    #[arg(long, default_value_t = 1)]
+    from: u64,
+    /// Block height to stop searching at.
+    /// Possible values:
+    /// "committed" (latest committed block),
+    /// "pending" (consider pending state changes),
+    /// or a specific block height, e.g., "123".
+    #[arg(long, value_parser = parse_query_height, default_value = "committed")]
+    to: FvmQueryHeight,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreDiffArgs {
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// The prefix to filter objects by.
+    #[arg(short, long, default_value = "")]
+    prefix: String,
+    /// First block height.
+    #[arg(long)]
+    from: u64,
+    /// Second block height.
+    #[arg(long)]
+    to: u64,
+}
+
 #[derive(Clone, Debug, Args)]
 struct ObjectstoreQueryArgs {
     /// Object store machine address.
@@ -167,8 +366,67 @@ struct ObjectstoreQueryArgs {
     #[arg(short, long, default_value_t = 0)]
     offset: u64,
     /// The maximum number of objects to list. '0' indicates max (10k).
+    /// With `--all`, this is the page size.
     #[arg(short, long, default_value_t = 0)]
     limit: u64,
+    /// List all matching objects by fetching pages until none remain.
+    /// All pages are queried at the height of the first page.
+    #[arg(long)]
+    all: bool,
+    /// Query block height.
+    /// Possible values:
+    /// "committed" (latest committed block),
+    /// "pending" (consider pending state changes),
+    /// or a specific block height, e.g., "123".
+    #[arg(long, value_parser = parse_query_height, default_value = "committed")]
+    height: FvmQueryHeight,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreSyncArgs {
+    /// Wallet private key (ECDSA, secp256k1) for signing transactions.
+    #[arg(short, long, env, value_parser = parse_secret_key)]
+    private_key: SecretKey,
+    /// Node Object API URL.
+    #[arg(long, env)]
+    object_api_url: Option<Url>,
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// Local directory to sync.
+    dir: PathBuf,
+    /// Key prefix that local paths are mapped under, e.g., "photos/".
+    #[arg(long, default_value = "")]
+    prefix: String,
+    /// Delete remote keys under the prefix that no longer exist locally.
+    #[arg(long)]
+    delete: bool,
+    /// Maximum number of files hashed and uploaded concurrently.
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
+    /// Broadcast mode for the transactions.
+    #[arg(short, long, value_enum, env, default_value_t = BroadcastMode::Commit)]
+    broadcast_mode: BroadcastMode,
+    #[command(flatten)]
+    tx_args: TxArgs,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreSyncDownArgs {
+    /// Node Object API URL.
+    #[arg(long, env)]
+    object_api_url: Option<Url>,
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// Local directory to sync into.
+    dir: PathBuf,
+    /// Key prefix to mirror, e.g., "photos/".
+    #[arg(long, default_value = "")]
+    prefix: String,
+    /// Maximum number of objects compared and downloaded concurrently.
+    #[arg(long, default_value_t = 4)]
+    concurrency: usize,
     /// Query block height.
     /// Possible values:
     /// "committed" (latest committed block),
@@ -178,6 +436,149 @@ struct ObjectstoreQueryArgs {
     height: FvmQueryHeight,
 }
 
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreExportArgs {
+    /// Node Object API URL.
+    #[arg(long, env)]
+    object_api_url: Option<Url>,
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// Output CAR file path.
+    output: PathBuf,
+    /// The prefix to filter objects by.
+    #[arg(long, default_value = "")]
+    prefix: String,
+    /// Query block height.
+    /// Possible values:
+    /// "committed" (latest committed block),
+    /// "pending" (consider pending state changes),
+    /// or a specific block height, e.g., "123".
+    #[arg(long, value_parser = parse_query_height, default_value = "committed")]
+    height: FvmQueryHeight,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreImportArgs {
+    /// Wallet private key (ECDSA, secp256k1) for signing transactions.
+    #[arg(short, long, env, value_parser = parse_secret_key)]
+    private_key: SecretKey,
+    /// Node Object API URL.
+    #[arg(long, env)]
+    object_api_url: Option<Url>,
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// CAR file path.
+    input: PathBuf,
+    /// Overwrite objects that already exist.
+    #[arg(short, long)]
+    overwrite: bool,
+    /// Broadcast mode for the transactions.
+    #[arg(short, long, value_enum, env, default_value_t = BroadcastMode::Commit)]
+    broadcast_mode: BroadcastMode,
+    #[command(flatten)]
+    tx_args: TxArgs,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreServeArgs {
+    /// Node Object API URL.
+    #[arg(long, env)]
+    object_api_url: Option<Url>,
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// Key prefix that request paths are mapped under, e.g., "site/".
+    #[arg(long, default_value = "")]
+    prefix: String,
+    /// Socket address to listen on.
+    #[arg(short, long, default_value = "127.0.0.1:8080")]
+    listen: SocketAddr,
+    /// Query block height.
+    /// Possible values:
+    /// "committed" (latest committed block),
+    /// "pending" (consider pending state changes),
+    /// or a specific block height, e.g., "123".
+    /// The height is resolved once at startup and pinned for all requests.
+    #[arg(long, value_parser = parse_query_height, default_value = "committed")]
+    height: FvmQueryHeight,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstorePresignArgs {
+    /// Wallet private key (ECDSA, secp256k1) for signing the upload.
+    #[arg(short, long, env, value_parser = parse_secret_key)]
+    private_key: SecretKey,
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// Key of the object to upload.
+    #[arg(short, long)]
+    key: String,
+    /// CID of the object's content, as computed by the `cid` command.
+    #[arg(long)]
+    cid: Cid,
+    /// Size of the object's content in bytes, as computed by the `cid` command.
+    #[arg(long)]
+    size: usize,
+    /// Overwrite the object if it already exists.
+    #[arg(short, long)]
+    overwrite: bool,
+    #[arg(short, long, value_parser = parse_metadata)]
+    metadata: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreUploadPresignedArgs {
+    /// Node Object API URL.
+    #[arg(long, env)]
+    object_api_url: Option<Url>,
+    /// Pre-signed upload file, as output by the `presign` command.
+    upload: PathBuf,
+    /// Input file containing the object's content.
+    input: PathBuf,
+}
+
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreAddPresignedArgs {
+    /// Wallet private key (ECDSA, secp256k1) for signing transactions.
+    #[arg(short, long, env, value_parser = parse_secret_key)]
+    private_key: SecretKey,
+    /// Pre-signed upload file, as output by the `presign` command.
+    upload: PathBuf,
+    /// Broadcast mode for the transaction.
+    #[arg(short, long, value_enum, env, default_value_t = BroadcastMode::Commit)]
+    broadcast_mode: BroadcastMode,
+    #[command(flatten)]
+    tx_args: TxArgs,
+}
+
+#[cfg(target_os = "linux")]
+#[derive(Clone, Debug, Args)]
+struct ObjectstoreMountArgs {
+    /// Node Object API URL.
+    #[arg(long, env)]
+    object_api_url: Option<Url>,
+    /// Object store machine address.
+    #[arg(short, long, value_parser = parse_address)]
+    address: Address,
+    /// Directory to mount the object store at.
+    mountpoint: PathBuf,
+    /// Size of the local block cache in MiB.
+    #[arg(long, default_value_t = 256)]
+    cache_size: usize,
+    /// Query block height.
+    /// Possible values:
+    /// "committed" (latest committed block),
+    /// "pending" (consider pending state changes),
+    /// or a specific block height, e.g., "123".
+    /// A specific block height pins the mounted view; otherwise, directories are refreshed
+    /// periodically.
+    #[arg(long, value_parser = parse_query_height, default_value = "committed")]
+    height: FvmQueryHeight,
+}
+
 /// Objectstore commmands handler.
 pub async fn handle_objectstore(cli: Cli, args: &ObjectstoreArgs) -> anyhow::Result<()> {
     let subnet_id = get_subnet_id(&cli)?;
@@ -240,28 +641,46 @@ pub async fn handle_objectstore(cli: Cli, args: &ObjectstoreArgs) -> anyhow::Res
             )?;
             signer.set_sequence(sequence, &provider).await?;
 
-            let file = File::open(&args.input).await?;
-            let md = file.metadata().await?;
-            if !md.is_file() {
-                return Err(anyhow!("input must be a file"));
-            }
+            let encryption_key = match &args.encryption_key_file {
+                Some(path) => Some(EncryptionKey::from_file(path).await?),
+                None => None,
+            };
+            let journal = args.journal.then(|| {
+                let mut path = args.input.clone().into_os_string();
+                path.push(".adm-journal");
+                PathBuf::from(path)
+            });
+            let mut options = AddOptions {
+                overwrite: args.overwrite,
+                broadcast_mode,
+                gas_params,
+                progress: get_progress(&cli),
+                metadata,
+                journal,
+                source_modified: None,
+                wait_resolved: args.wait_resolved.then_some(args.resolve_timeout),
+                encryption_key,
+                compression: args.compress.map(|c| c.get()),
+                skip_unchanged: args.skip_unchanged,
+                if_match: args.if_match,
+            };
 
             let machine = ObjectStore::attach(args.address);
-            let tx = machine
-                .add(
-                    &provider,
-                    &mut signer,
-                    &args.key,
-                    file,
-                    AddOptions {
-                        overwrite: args.overwrite,
-                        broadcast_mode,
-                        gas_params,
-                        show_progress: !cli.quiet,
-                        metadata,
-                    },
-                )
-                .await?;
+            let tx = if args.input == Path::new("-") {
+                machine
+                    .add_reader(&provider, &mut signer, &args.key, io::stdin(), options)
+                    .await?
+            } else {
+                let file = File::open(&args.input).await?;
+                let md = file.metadata().await?;
+                if !md.is_file() {
+                    return Err(anyhow!("input must be a file"));
+                }
+                options.source_modified = md.modified().ok();
+                machine
+                    .add(&provider, &mut signer, &args.key, file, options)
+                    .await?
+            };
 
             print_json(&tx)
         }
@@ -282,14 +701,42 @@ pub async fn handle_objectstore(cli: Cli, args: &ObjectstoreArgs) -> anyhow::Res
             signer.set_sequence(sequence, &provider).await?;
 
             let machine = ObjectStore::attach(args.address);
+            if let Some(prefix) = &args.prefix {
+                let summary = machine
+                    .delete_prefix(
+                        &provider,
+                        &mut signer,
+                        prefix,
+                        DeletePrefixOptions {
+                            dry_run: args.dry_run,
+                            broadcast_mode,
+                            gas_params,
+                            progress: get_progress(&cli),
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+
+                print_json(&summary)?;
+                if !summary.failed.is_empty() {
+                    return Err(anyhow!("failed to delete {} objects", summary.failed.len()));
+                }
+                return Ok(());
+            }
+
+            let key = args
+                .key
+                .as_deref()
+                .ok_or_else(|| anyhow!("a key or --prefix is required"))?;
             let tx = machine
                 .delete(
                     &provider,
                     &mut signer,
-                    &args.key,
+                    key,
                     DeleteOptions {
                         broadcast_mode,
                         gas_params,
+                        if_match: args.if_match,
                     },
                 )
                 .await?;
@@ -305,6 +752,23 @@ pub async fn handle_objectstore(cli: Cli, args: &ObjectstoreArgs) -> anyhow::Res
                 JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, Some(object_api_url))?;
 
             let machine = ObjectStore::attach(args.address);
+            let encryption_key = match &args.encryption_key_file {
+                Some(path) => Some(EncryptionKey::from_file(path).await?),
+                None => None,
+            };
+            if let Some(output) = &args.output {
+                return machine
+                    .download_to_path(
+                        &provider,
+                        &args.key,
+                        output,
+                        DownloadOptions {
+                            height: args.height,
+                            progress: get_progress(&cli),
+                        },
+                    )
+                    .await;
+            }
             machine
                 .get(
                     &provider,
@@ -313,28 +777,126 @@ pub async fn handle_objectstore(cli: Cli, args: &ObjectstoreArgs) -> anyhow::Res
                     GetOptions {
                         range: args.range.clone(),
                         height: args.height,
-                        show_progress: true,
+                        progress: get_progress(&cli),
+                        verify: !args.no_verify,
+                        parallel: args.parallel,
+                        encryption_key,
+                        raw: args.raw,
                     },
                 )
                 .await
         }
-        ObjectstoreCommands::Query(args) => {
+        ObjectstoreCommands::Stat(args) => {
+            let object_api_url = args
+                .object_api_url
+                .clone()
+                .unwrap_or(cli.network.get().object_api_url()?);
+            let provider =
+                JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, Some(object_api_url))?;
+
+            let machine = ObjectStore::attach(args.address);
+            let head = machine
+                .head(&provider, &args.key, args.height)
+                .await?
+                .ok_or_else(|| anyhow!("object not found for key '{}'", args.key))?;
+
+            let cid = cid::Cid::try_from(head.object.cid.0)?;
+            print_json(&json!({
+                "key": head.key,
+                "cid": cid.to_string(),
+                "resolved": head.object.resolved,
+                "size": head.object.size,
+                "metadata": head.object.metadata,
+                "content_length": head.content_length,
+                "height": head.height,
+            }))
+        }
+        ObjectstoreCommands::Cid(args) => {
+            let (cid, size) = if args.input == Path::new("-") {
+                compute_cid(&mut io::stdin()).await?
+            } else {
+                compute_cid(&mut File::open(&args.input).await?).await?
+            };
+
+            print_json(&json!({"cid": cid.to_string(), "size": size}))
+        }
+        ObjectstoreCommands::Verify(args) => {
             let provider = JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, None)?;
 
             let machine = ObjectStore::attach(args.address);
-            let list = machine
-                .query(
-                    &provider,
-                    QueryOptions {
-                        prefix: args.prefix.clone(),
-                        delimiter: args.delimiter.clone(),
-                        offset: args.offset,
-                        limit: args.limit,
-                        height: args.height,
-                    },
-                )
+            let verification = if args.input == Path::new("-") {
+                machine
+                    .verify(&provider, &args.key, io::stdin(), args.height)
+                    .await?
+            } else {
+                let file = File::open(&args.input).await?;
+                machine
+                    .verify(&provider, &args.key, file, args.height)
+                    .await?
+            };
+
+            print_json(&verification)?;
+            if !verification.matches {
+                return Err(anyhow!(
+                    "local cid {} does not match object cid {}",
+                    verification.local_cid,
+                    verification.remote_cid
+                ));
+            }
+            Ok(())
+        }
+        ObjectstoreCommands::History(args) => {
+            let provider = JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, None)?;
+
+            let machine = ObjectStore::attach(args.address);
+            let versions = machine
+                .history(&provider, &args.key, args.from, args.to)
+                .await?;
+
+            print_json(&versions)
+        }
+        ObjectstoreCommands::Diff(args) => {
+            let provider = JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, None)?;
+
+            let machine = ObjectStore::attach(args.address);
+            let diff = machine
+                .diff(&provider, args.from, args.to, &args.prefix)
                 .await?;
 
+            print_json(&diff)
+        }
+        ObjectstoreCommands::Query(args) => {
+            let provider = JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, None)?;
+
+            let machine = ObjectStore::attach(args.address);
+            let options = QueryOptions {
+                prefix: args.prefix.clone(),
+                delimiter: args.delimiter.clone(),
+                offset: args.offset,
+                limit: args.limit,
+                height: args.height,
+            };
+            let list = if args.all {
+                let mut list = ObjectList {
+                    objects: Vec::new(),
+                    common_prefixes: Vec::new(),
+                };
+                let mut items = pin!(machine.query_stream(&provider, options));
+                while let Some(item) = items.next().await {
+                    match item?.value {
+                        QueryItem::Object(key, object) => {
+                            list.objects.push((key.into_bytes(), object))
+                        }
+                        QueryItem::CommonPrefix(prefix) => {
+                            list.common_prefixes.push(prefix.into_bytes())
+                        }
+                    }
+                }
+                list
+            } else {
+                machine.query(&provider, options).await?
+            };
+
             let objects = list
                 .objects
                 .iter()
@@ -353,5 +915,241 @@ pub async fn handle_objectstore(cli: Cli, args: &ObjectstoreArgs) -> anyhow::Res
 
             print_json(&json!({"objects": objects, "common_prefixes": common_prefixes}))
         }
+        ObjectstoreCommands::Sync(args) => {
+            let object_api_url = args
+                .object_api_url
+                .clone()
+                .unwrap_or(cli.network.get().object_api_url()?);
+            let provider =
+                JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, Some(object_api_url))?;
+
+            let broadcast_mode = args.broadcast_mode.get();
+            let TxParams {
+                sequence,
+                gas_params,
+            } = args.tx_args.to_tx_params();
+
+            let mut signer = Wallet::new_secp256k1(
+                args.private_key.clone(),
+                AccountKind::Ethereum,
+                subnet_id.clone(),
+            )?;
+            signer.set_sequence(sequence, &provider).await?;
+
+            let machine = ObjectStore::attach(args.address);
+            let summary = machine
+                .sync_up(
+                    &provider,
+                    &mut signer,
+                    &args.dir,
+                    SyncOptions {
+                        prefix: args.prefix.clone(),
+                        delete: args.delete,
+                        concurrency: args.concurrency,
+                        broadcast_mode,
+                        gas_params,
+                        progress: get_progress(&cli),
+                    },
+                )
+                .await?;
+
+            print_json(&summary)
+        }
+        ObjectstoreCommands::SyncDown(args) => {
+            let object_api_url = args
+                .object_api_url
+                .clone()
+                .unwrap_or(cli.network.get().object_api_url()?);
+            let provider =
+                JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, Some(object_api_url))?;
+
+            let machine = ObjectStore::attach(args.address);
+            let summary = machine
+                .sync_down(
+                    &provider,
+                    &args.dir,
+                    SyncDownOptions {
+                        prefix: args.prefix.clone(),
+                        height: args.height,
+                        concurrency: args.concurrency,
+                        progress: get_progress(&cli),
+                    },
+                )
+                .await?;
+
+            print_json(&summary)
+        }
+        ObjectstoreCommands::Export(args) => {
+            let object_api_url = args
+                .object_api_url
+                .clone()
+                .unwrap_or(cli.network.get().object_api_url()?);
+            let provider =
+                JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, Some(object_api_url))?;
+
+            let machine = ObjectStore::attach(args.address);
+            let file = File::create(&args.output).await?;
+            let summary = machine
+                .export(
+                    &provider,
+                    io::BufWriter::new(file),
+                    ExportOptions {
+                        prefix: args.prefix.clone(),
+                        height: args.height,
+                        progress: get_progress(&cli),
+                    },
+                )
+                .await?;
+
+            print_json(&summary)
+        }
+        ObjectstoreCommands::Import(args) => {
+            let object_api_url = args
+                .object_api_url
+                .clone()
+                .unwrap_or(cli.network.get().object_api_url()?);
+            let provider =
+                JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, Some(object_api_url))?;
+
+            let broadcast_mode = args.broadcast_mode.get();
+            let TxParams {
+                sequence,
+                gas_params,
+            } = args.tx_args.to_tx_params();
+
+            let mut signer = Wallet::new_secp256k1(
+                args.private_key.clone(),
+                AccountKind::Ethereum,
+                subnet_id.clone(),
+            )?;
+            signer.set_sequence(sequence, &provider).await?;
+
+            let machine = ObjectStore::attach(args.address);
+            let summary = machine
+                .import(
+                    &provider,
+                    &mut signer,
+                    &args.input,
+                    ImportOptions {
+                        overwrite: args.overwrite,
+                        broadcast_mode,
+                        gas_params,
+                        progress: get_progress(&cli),
+                    },
+                )
+                .await?;
+
+            print_json(&summary)
+        }
+        ObjectstoreCommands::Serve(args) => {
+            let object_api_url = args
+                .object_api_url
+                .clone()
+                .unwrap_or(cli.network.get().object_api_url()?);
+            let provider =
+                JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, Some(object_api_url))?;
+
+            let machine = ObjectStore::attach(args.address);
+            serve::serve(
+                provider,
+                machine,
+                args.prefix.clone(),
+                args.height,
+                args.listen,
+            )
+            .await
+        }
+        ObjectstoreCommands::Presign(args) => {
+            let signer = Wallet::new_secp256k1(
+                args.private_key.clone(),
+                AccountKind::Ethereum,
+                subnet_id.clone(),
+            )?;
+
+            let machine = ObjectStore::attach(args.address);
+            let upload = machine.presign(
+                &signer,
+                &args.key,
+                args.cid,
+                args.size,
+                PresignOptions {
+                    overwrite: args.overwrite,
+                    metadata: args.metadata.clone().into_iter().collect(),
+                },
+            )?;
+
+            print_json(&upload)
+        }
+        ObjectstoreCommands::UploadPresigned(args) => {
+            let object_api_url = args
+                .object_api_url
+                .clone()
+                .unwrap_or(cli.network.get().object_api_url()?);
+            let provider =
+                JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, Some(object_api_url))?;
+
+            let upload = read_presigned(&args.upload).await?;
+            let file = File::open(&args.input).await?;
+            let md = file.metadata().await?;
+            if !md.is_file() {
+                return Err(anyhow!("input must be a file"));
+            }
+
+            let machine = ObjectStore::attach(parse_address(&upload.address)?);
+            let cid = machine
+                .upload_presigned(&provider, &upload, file, get_progress(&cli))
+                .await?;
+
+            print_json(&json!({"key": upload.key, "cid": cid.to_string(), "size": upload.size}))
+        }
+        ObjectstoreCommands::AddPresigned(args) => {
+            let provider = JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, None)?;
+
+            let broadcast_mode = args.broadcast_mode.get();
+            let TxParams {
+                sequence,
+                gas_params,
+            } = args.tx_args.to_tx_params();
+
+            let mut signer = Wallet::new_secp256k1(
+                args.private_key.clone(),
+                AccountKind::Ethereum,
+                subnet_id.clone(),
+            )?;
+            signer.set_sequence(sequence, &provider).await?;
+
+            let upload = read_presigned(&args.upload).await?;
+            let machine = ObjectStore::attach(parse_address(&upload.address)?);
+            let tx = machine
+                .add_presigned(&provider, &mut signer, &upload, broadcast_mode, gas_params)
+                .await?;
+
+            print_json(&tx)
+        }
+        #[cfg(target_os = "linux")]
+        ObjectstoreCommands::Mount(args) => {
+            let object_api_url = args
+                .object_api_url
+                .clone()
+                .unwrap_or(cli.network.get().object_api_url()?);
+            let provider =
+                JsonRpcProvider::new_http(get_rpc_url(&cli)?, None, Some(object_api_url))?;
+
+            let machine = ObjectStore::attach(args.address);
+            mount::mount(
+                provider,
+                machine,
+                args.mountpoint.clone(),
+                args.height,
+                args.cache_size,
+            )
+            .await
+        }
     }
 }
+
+/// Read a pre-signed upload from a file output by the `presign` command.
+async fn read_presigned(path: &Path) -> anyhow::Result<PresignedUpload> {
+    let data = tokio::fs::read(path).await?;
+    serde_json::from_slice(&data).map_err(|e| anyhow!("invalid pre-signed upload: {e}"))
+}
@@ -0,0 +1,508 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsStr;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use anyhow::anyhow;
+use bytes::Bytes;
+use fendermint_vm_message::query::FvmQueryHeight;
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEntry,
+    Request, FUSE_ROOT_ID,
+};
+use futures::TryStreamExt;
+use tokio::runtime::Handle;
+
+use adm_provider::{json_rpc::JsonRpcProvider, object::ObjectProvider, query::QueryProvider};
+use adm_sdk::machine::{
+    objectstore::{ObjectStore, QueryItem, QueryOptions},
+    Machine,
+};
+
+/// Size of the blocks that reads are fetched and cached in.
+const BLOCK_SIZE: u64 = 1024 * 1024;
+/// How long the kernel may cache entries and attributes.
+const ATTR_TTL: Duration = Duration::from_secs(1);
+/// How long a directory listing is reused before it's queried again.
+/// Listings at a pinned height never change, so they're never queried again.
+const LISTING_TTL: Duration = Duration::from_secs(10);
+
+/// A directory or object in the mounted tree.
+enum Node {
+    Directory {
+        prefix: String,
+        parent: u64,
+        listing: Option<Listing>,
+    },
+    File {
+        key: String,
+        size: u64,
+        cid: Vec<u8>,
+        height: u64,
+    },
+}
+
+/// The entries of a directory, by name, as of the time they were queried.
+struct Listing {
+    entries: BTreeMap<String, u64>,
+    queried: Instant,
+}
+
+/// A read-only view of an object store, where common prefixes are directories
+/// and objects are files.
+struct ObjectStoreFs {
+    runtime: Handle,
+    provider: JsonRpcProvider,
+    machine: ObjectStore,
+    height: FvmQueryHeight,
+    nodes: HashMap<u64, Node>,
+    inodes: HashMap<String, u64>,
+    cache: BlockCache,
+}
+
+/// Mount an object store at a directory until the process is interrupted.
+///
+/// If the height is not a specific block height, directory listings are refreshed periodically
+/// and each file is read at the height it was last listed at.
+pub async fn mount(
+    provider: JsonRpcProvider,
+    machine: ObjectStore,
+    mountpoint: PathBuf,
+    height: FvmQueryHeight,
+    cache_size: usize,
+) -> anyhow::Result<()> {
+    let root = Node::Directory {
+        prefix: String::new(),
+        parent: FUSE_ROOT_ID,
+        listing: None,
+    };
+    let address = machine.address();
+    let fs = ObjectStoreFs {
+        runtime: Handle::current(),
+        provider,
+        machine,
+        height,
+        nodes: HashMap::from([(FUSE_ROOT_ID, root)]),
+        inodes: HashMap::from([(String::new(), FUSE_ROOT_ID)]),
+        cache: BlockCache::new(cache_size),
+    };
+    let options = [
+        MountOption::RO,
+        MountOption::FSName(address.to_string()),
+        MountOption::Subtype("adm".into()),
+    ];
+    let session = fuser::spawn_mount2(fs, &mountpoint, &options)?;
+    println!(
+        "Mounted {} at {}; press Ctrl-C to unmount",
+        address,
+        mountpoint.display()
+    );
+    tokio::signal::ctrl_c().await?;
+    // Dropping the session unmounts the filesystem
+    drop(session);
+    Ok(())
+}
+
+impl ObjectStoreFs {
+    /// Query a directory's entries unless its listing is recent enough.
+    async fn refresh(&mut self, ino: u64) -> anyhow::Result<()> {
+        let prefix = match self.nodes.get(&ino) {
+            Some(Node::Directory { listing, .. })
+                if listing.as_ref().is_some_and(|listing| {
+                    matches!(self.height, FvmQueryHeight::Height(_))
+                        || listing.queried.elapsed() < LISTING_TTL
+                }) =>
+            {
+                return Ok(())
+            }
+            Some(Node::Directory { prefix, .. }) => prefix.clone(),
+            _ => return Ok(()),
+        };
+
+        // Resolve the height first so files are read at the height they were listed at
+        let height = match self.height {
+            FvmQueryHeight::Height(height) => height,
+            height => self
+                .provider
+                .actor_state(&self.machine.address(), height)
+                .await?
+                .height
+                .value(),
+        };
+        let options = QueryOptions {
+            prefix: prefix.clone(),
+            delimiter: "/".into(),
+            height: FvmQueryHeight::Height(height),
+            ..Default::default()
+        };
+        let items: Vec<QueryItem> = self
+            .machine
+            .query_stream(&self.provider, options)
+            .map_ok(|item| item.value)
+            .try_collect()
+            .await?;
+
+        // An object can share its name with a common prefix, e.g., "a" and "a/". Only one entry
+        // can hold the name, so the directory keeps it and the object is hidden.
+        let directories: HashSet<String> = items
+            .iter()
+            .filter_map(|item| match item {
+                QueryItem::CommonPrefix(common_prefix) => Some(common_prefix.clone()),
+                QueryItem::Object(..) => None,
+            })
+            .collect();
+        let mut entries = BTreeMap::new();
+        for item in items {
+            let (path, node) = match item {
+                QueryItem::Object(key, _) if directories.contains(&format!("{}/", key)) => {
+                    tracing::warn!(
+                        "hiding object '{}', which has the same name as directory '{}/'",
+                        key,
+                        key
+                    );
+                    continue;
+                }
+                QueryItem::Object(key, object) => {
+                    let node = Node::File {
+                        key: key.clone(),
+                        size: object.size as u64,
+                        cid: object.cid.0,
+                        height,
+                    };
+                    (key, node)
+                }
+                QueryItem::CommonPrefix(common_prefix) => {
+                    let node = Node::Directory {
+                        prefix: common_prefix.clone(),
+                        parent: ino,
+                        listing: None,
+                    };
+                    (common_prefix, node)
+                }
+            };
+            let name = path[prefix.len()..].trim_end_matches('/').to_string();
+            if name.is_empty() {
+                continue;
+            }
+            let child = self.inode(&path);
+            match self.nodes.get(&child) {
+                // Keep the cached listings of known directories
+                Some(Node::Directory { .. }) if matches!(node, Node::Directory { .. }) => {}
+                _ => {
+                    self.nodes.insert(child, node);
+                }
+            }
+            entries.insert(name, child);
+        }
+        if let Some(Node::Directory { listing, .. }) = self.nodes.get_mut(&ino) {
+            *listing = Some(Listing {
+                entries,
+                queried: Instant::now(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the inode for a key or common prefix, allocating one if it's new.
+    fn inode(&mut self, path: &str) -> u64 {
+        let next = self.inodes.len() as u64 + FUSE_ROOT_ID;
+        *self.inodes.entry(path.to_string()).or_insert(next)
+    }
+
+    /// Look up a directory entry by name.
+    async fn lookup_entry(&mut self, parent: u64, name: &str) -> anyhow::Result<Option<u64>> {
+        self.refresh(parent).await?;
+        Ok(match self.nodes.get(&parent) {
+            Some(Node::Directory {
+                listing: Some(listing),
+                ..
+            }) => listing.entries.get(name).copied(),
+            _ => None,
+        })
+    }
+
+    /// Read a range of a file through the block cache.
+    async fn read_range(&mut self, ino: u64, offset: u64, size: u64) -> anyhow::Result<Vec<u8>> {
+        let (key, object_size, cid, height) = match self.nodes.get(&ino) {
+            Some(Node::File {
+                key,
+                size,
+                cid,
+                height,
+            }) => (key.clone(), *size, cid.clone(), *height),
+            _ => return Ok(Vec::new()),
+        };
+        let blocks = read_blocks(offset, size, object_size);
+        let mut data = Vec::with_capacity(blocks.iter().map(|(_, range)| range.len()).sum());
+        for (index, range) in blocks {
+            let block = match self.cache.get(&cid, index) {
+                Some(block) => block,
+                None => {
+                    let (start, last) = block_range(index, object_size);
+                    let range = Some(format!("{}-{}", start, last));
+                    let response = self
+                        .provider
+                        .download(self.machine.address(), &key, range, height)
+                        .await?;
+                    let block = response.bytes().await?;
+                    if block.len() as u64 != last - start + 1 {
+                        return Err(anyhow!(
+                            "unexpected block size for range {}-{}: {} bytes",
+                            start,
+                            last,
+                            block.len()
+                        ));
+                    }
+                    self.cache.insert(&cid, index, block.clone());
+                    block
+                }
+            };
+            data.extend_from_slice(&block[range]);
+        }
+        Ok(data)
+    }
+
+    /// Returns the attributes of a node.
+    fn attr(&self, req: &Request<'_>, ino: u64) -> Option<FileAttr> {
+        // Objects don't record when they were written, so times are the Unix epoch
+        let (kind, perm, size) = match self.nodes.get(&ino)? {
+            Node::Directory { .. } => (FileType::Directory, 0o555, 0),
+            Node::File { size, .. } => (FileType::RegularFile, 0o444, *size),
+        };
+        Some(FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: UNIX_EPOCH,
+            mtime: UNIX_EPOCH,
+            ctime: UNIX_EPOCH,
+            crtime: UNIX_EPOCH,
+            kind,
+            perm,
+            nlink: if kind == FileType::Directory { 2 } else { 1 },
+            uid: req.uid(),
+            gid: req.gid(),
+            rdev: 0,
+            blksize: BLOCK_SIZE as u32,
+            flags: 0,
+        })
+    }
+}
+
+impl Filesystem for ObjectStoreFs {
+    fn lookup(&mut self, req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let name = match name.to_str() {
+            Some(name) => name,
+            None => return reply.error(libc::ENOENT),
+        };
+        let runtime = self.runtime.clone();
+        match runtime.block_on(self.lookup_entry(parent, name)) {
+            Ok(Some(ino)) => match self.attr(req, ino) {
+                Some(attr) => reply.entry(&ATTR_TTL, &attr, 0),
+                None => reply.error(libc::ENOENT),
+            },
+            Ok(None) => reply.error(libc::ENOENT),
+            Err(e) => {
+                tracing::error!("failed to look up '{}': {:#}", name, e);
+                reply.error(libc::EIO)
+            }
+        }
+    }
+
+    fn getattr(&mut self, req: &Request<'_>, ino: u64, reply: ReplyAttr) {
+        match self.attr(req, ino) {
+            Some(attr) => reply.attr(&ATTR_TTL, &attr),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let runtime = self.runtime.clone();
+        match runtime.block_on(self.read_range(ino, offset.max(0) as u64, size as u64)) {
+            Ok(data) => reply.data(&data),
+            Err(e) => {
+                tracing::error!("failed to read inode {}: {:#}", ino, e);
+                reply.error(libc::EIO)
+            }
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let runtime = self.runtime.clone();
+        if let Err(e) = runtime.block_on(self.refresh(ino)) {
+            tracing::error!("failed to list inode {}: {:#}", ino, e);
+            return reply.error(libc::EIO);
+        }
+        let (parent, listing) = match self.nodes.get(&ino) {
+            Some(Node::Directory {
+                parent,
+                listing: Some(listing),
+                ..
+            }) => (*parent, listing),
+            Some(Node::File { .. }) => return reply.error(libc::ENOTDIR),
+            _ => return reply.error(libc::ENOENT),
+        };
+
+        let mut entries = vec![
+            (ino, FileType::Directory, "."),
+            (parent, FileType::Directory, ".."),
+        ];
+        for (name, child) in &listing.entries {
+            let kind = match self.nodes.get(child) {
+                Some(Node::Directory { .. }) => FileType::Directory,
+                _ => FileType::RegularFile,
+            };
+            entries.push((*child, kind, name.as_str()));
+        }
+        for (i, (child, kind, name)) in entries.into_iter().enumerate().skip(offset as usize) {
+            // The offset of an entry is the offset to resume listing from
+            if reply.add(child, (i + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok()
+    }
+}
+
+/// Returns the blocks covering a read of `size` bytes at `offset` into an object of
+/// `object_size` bytes, as each block's index and the range of the block to read.
+fn read_blocks(offset: u64, size: u64, object_size: u64) -> Vec<(u64, Range<usize>)> {
+    let end = object_size.min(offset.saturating_add(size));
+    if offset >= end {
+        return Vec::new();
+    }
+    (offset / BLOCK_SIZE..=(end - 1) / BLOCK_SIZE)
+        .map(|index| {
+            let start = index * BLOCK_SIZE;
+            let from = offset.max(start) - start;
+            let to = end.min(start + BLOCK_SIZE) - start;
+            (index, from as usize..to as usize)
+        })
+        .collect()
+}
+
+/// Returns the first and last byte offsets of a block within an object.
+fn block_range(index: u64, object_size: u64) -> (u64, u64) {
+    let start = index * BLOCK_SIZE;
+    (start, object_size.min(start + BLOCK_SIZE) - 1)
+}
+
+/// A bounded cache of object blocks, keyed by object CID, that evicts the least recently used
+/// block when full.
+struct BlockCache {
+    blocks: HashMap<(Vec<u8>, u64), (Bytes, u64)>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl BlockCache {
+    /// Create a cache holding up to the given number of MiB.
+    fn new(size: usize) -> Self {
+        Self {
+            blocks: HashMap::new(),
+            capacity: (size as u64 * 1024 * 1024 / BLOCK_SIZE) as usize,
+            clock: 0,
+        }
+    }
+
+    fn get(&mut self, cid: &[u8], index: u64) -> Option<Bytes> {
+        self.clock += 1;
+        let (block, used) = self.blocks.get_mut(&(cid.to_vec(), index))?;
+        *used = self.clock;
+        Some(block.clone())
+    }
+
+    fn insert(&mut self, cid: &[u8], index: u64, block: Bytes) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.blocks.len() >= self.capacity {
+            let oldest = self
+                .blocks
+                .iter()
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(id, _)| id.clone());
+            if let Some(oldest) = oldest {
+                self.blocks.remove(&oldest);
+            }
+        }
+        self.clock += 1;
+        self.blocks
+            .insert((cid.to_vec(), index), (block, self.clock));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIB: u64 = 1024 * 1024;
+
+    #[test]
+    fn test_read_blocks() {
+        let size = 2 * BLOCK_SIZE + 100;
+        assert_eq!(read_blocks(0, 10, size), vec![(0, 0..10)]);
+        assert_eq!(
+            read_blocks(BLOCK_SIZE - 5, 10, size),
+            vec![
+                (0, (BLOCK_SIZE - 5) as usize..BLOCK_SIZE as usize),
+                (1, 0..5)
+            ]
+        );
+        assert_eq!(
+            read_blocks(BLOCK_SIZE, 2 * BLOCK_SIZE, size),
+            vec![(1, 0..BLOCK_SIZE as usize), (2, 0..100)]
+        );
+        assert_eq!(read_blocks(size - 1, u64::MAX, size), vec![(2, 99..100)]);
+        assert!(read_blocks(size, 10, size).is_empty());
+        assert!(read_blocks(0, 0, size).is_empty());
+        assert!(read_blocks(0, 10, 0).is_empty());
+    }
+
+    #[test]
+    fn test_block_range() {
+        let size = 2 * BLOCK_SIZE + 100;
+        assert_eq!(block_range(0, size), (0, BLOCK_SIZE - 1));
+        assert_eq!(block_range(1, size), (BLOCK_SIZE, 2 * BLOCK_SIZE - 1));
+        assert_eq!(block_range(2, size), (2 * BLOCK_SIZE, size - 1));
+    }
+
+    #[test]
+    fn test_block_cache_eviction() {
+        let mut cache = BlockCache::new((2 * BLOCK_SIZE / MIB) as usize);
+        assert_eq!(cache.capacity, 2);
+        cache.insert(b"a", 0, Bytes::from_static(b"a0"));
+        cache.insert(b"a", 1, Bytes::from_static(b"a1"));
+        // Reading a0 makes a1 the least recently used
+        assert_eq!(cache.get(b"a", 0), Some(Bytes::from_static(b"a0")));
+        cache.insert(b"b", 0, Bytes::from_static(b"b0"));
+        assert_eq!(cache.get(b"a", 1), None);
+        assert_eq!(cache.get(b"a", 0), Some(Bytes::from_static(b"a0")));
+        assert_eq!(cache.get(b"b", 0), Some(Bytes::from_static(b"b0")));
+
+        let mut cache = BlockCache::new(0);
+        cache.insert(b"a", 0, Bytes::from_static(b"a0"));
+        assert_eq!(cache.get(b"a", 0), None);
+    }
+}
@@ -0,0 +1,310 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use fendermint_vm_message::query::FvmQueryHeight;
+use tokio::net::TcpListener;
+use tokio_util::io::ReaderStream;
+
+use adm_provider::{json_rpc::JsonRpcProvider, query::QueryProvider};
+use adm_sdk::machine::{
+    objectstore::{parse_range, GetOptions, ObjectStore, CONTENT_ENCODING_KEY, CONTENT_TYPE_KEY},
+    Machine,
+};
+
+/// Object served for directory paths.
+const INDEX_DOCUMENT: &str = "index.html";
+/// Size of the buffer between an object download and its response body.
+const BODY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// An object store prefix served over HTTP at a pinned height.
+struct Site {
+    provider: JsonRpcProvider,
+    machine: ObjectStore,
+    prefix: String,
+    height: u64,
+}
+
+/// Serve the objects under a prefix over HTTP until the process is stopped.
+///
+/// Committed or pending heights are resolved to a block height once, at startup, so every
+/// request reads the same version of the site.
+pub async fn serve(
+    provider: JsonRpcProvider,
+    machine: ObjectStore,
+    prefix: String,
+    height: FvmQueryHeight,
+    listen: SocketAddr,
+) -> anyhow::Result<()> {
+    let height = match height {
+        FvmQueryHeight::Height(height) => height,
+        height => provider
+            .actor_state(&machine.address(), height)
+            .await?
+            .height
+            .value(),
+    };
+    let site = Arc::new(Site {
+        provider,
+        machine,
+        prefix,
+        height,
+    });
+    let router = Router::new()
+        .route("/", get(serve_root))
+        .route("/*path", get(serve_path))
+        .with_state(site.clone());
+
+    let listener = TcpListener::bind(listen).await?;
+    println!(
+        "Serving '{}' at height {} on http://{}",
+        site.prefix, site.height, listen
+    );
+    axum::serve(listener, router).await?;
+    Ok(())
+}
+
+async fn serve_root(State(site): State<Arc<Site>>, method: Method, headers: HeaderMap) -> Response {
+    respond(site, String::new(), method, headers).await
+}
+
+async fn serve_path(
+    State(site): State<Arc<Site>>,
+    Path(path): Path<String>,
+    method: Method,
+    headers: HeaderMap,
+) -> Response {
+    respond(site, path, method, headers).await
+}
+
+async fn respond(site: Arc<Site>, path: String, method: Method, headers: HeaderMap) -> Response {
+    match serve_object(site, path.clone(), method, headers).await {
+        Ok(response) => response,
+        Err(e) => {
+            // Errors can carry node and key details, so they're only logged
+            tracing::error!("failed to serve '{}': {:#}", path, e);
+            (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error\n").into_response()
+        }
+    }
+}
+
+/// Serve the object for a request path.
+///
+/// Paths that end in `/` are served from their index document. Paths without a matching object
+/// are redirected to their directory if it has an index document, so relative links resolve.
+async fn serve_object(
+    site: Arc<Site>,
+    path: String,
+    method: Method,
+    headers: HeaderMap,
+) -> anyhow::Result<Response> {
+    let height = FvmQueryHeight::Height(site.height);
+    let key = if path.is_empty() || path.ends_with('/') {
+        format!("{}{}{}", site.prefix, path, INDEX_DOCUMENT)
+    } else {
+        format!("{}{}", site.prefix, path)
+    };
+    let head = match site.machine.head(&site.provider, &key, height).await? {
+        Some(head) => head,
+        None => {
+            let index = format!("{}/{}", key, INDEX_DOCUMENT);
+            if !path.ends_with('/')
+                && site
+                    .machine
+                    .head(&site.provider, &index, height)
+                    .await?
+                    .is_some()
+            {
+                let location = format!("/{}/", encode_path(&path));
+                return Ok((
+                    StatusCode::MOVED_PERMANENTLY,
+                    [(header::LOCATION, location)],
+                )
+                    .into_response());
+            }
+            return Ok((StatusCode::NOT_FOUND, "Not found\n").into_response());
+        }
+    };
+    let size = match head.content_length {
+        Some(size) => size,
+        None => {
+            return Ok((
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Object is not resolved yet\n",
+            )
+                .into_response())
+        }
+    };
+
+    let etag = format!("\"{}\"", cid::Cid::try_from(head.object.cid.0.as_slice())?);
+    let mut response = Response::builder()
+        .header(header::ETAG, &etag)
+        .header(header::ACCEPT_RANGES, "bytes")
+        .header(
+            header::CONTENT_TYPE,
+            content_type(&head.key, &head.object.metadata),
+        );
+    if let Some(encoding) = head.object.metadata.get(CONTENT_ENCODING_KEY) {
+        response = response.header(header::CONTENT_ENCODING, encoding);
+    }
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| matches_etag(tag.trim(), &etag)));
+    if not_modified {
+        return Ok(response
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Body::empty())?);
+    }
+
+    let range = match headers.get(header::RANGE) {
+        Some(value) => match value
+            .to_str()
+            .ok()
+            .and_then(|value| parse_range(value, size))
+        {
+            Some(range) => Some(range),
+            None => {
+                return Ok(response
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{}", size))
+                    .body(Body::empty())?)
+            }
+        },
+        None => None,
+    };
+    response = match range {
+        Some((start, end)) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_LENGTH, end - start + 1)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, size),
+            ),
+        None => response.header(header::CONTENT_LENGTH, size),
+    };
+    if method == Method::HEAD {
+        return Ok(response.body(Body::empty())?);
+    }
+
+    // Objects are served as stored, leaving decompression to the client
+    let (reader, writer) = tokio::io::duplex(BODY_BUFFER_SIZE);
+    let options = GetOptions {
+        range: range.map(|(start, end)| format!("{}-{}", start, end)),
+        height,
+        raw: true,
+        ..Default::default()
+    };
+    tokio::spawn(async move {
+        // The response is already underway, so errors can only end the body early
+        if let Err(e) = site
+            .machine
+            .get(&site.provider, &key, writer, options)
+            .await
+        {
+            tracing::error!("failed to get object '{}': {:#}", key, e);
+        }
+    });
+    Ok(response.body(Body::from_stream(ReaderStream::new(reader)))?)
+}
+
+/// Returns an object's content type from its metadata or key extension.
+fn content_type(key: &str, metadata: &HashMap<String, String>) -> String {
+    if let Some(content_type) = metadata.get(CONTENT_TYPE_KEY) {
+        return content_type.clone();
+    }
+    let extension = key
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+    let content_type = match extension.as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" | "map" => "application/json",
+        "txt" | "md" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "ico" => "image/x-icon",
+        "pdf" => "application/pdf",
+        "wasm" => "application/wasm",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    };
+    content_type.to_string()
+}
+
+/// Percent-encode a decoded request path for use in a URL, keeping its `/` separators.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Returns whether an `If-None-Match` entry matches an ETag, ignoring weak validators.
+fn matches_etag(tag: &str, etag: &str) -> bool {
+    tag == "*" || tag.strip_prefix("W/").unwrap_or(tag) == etag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type() {
+        let metadata = HashMap::new();
+        assert_eq!(
+            content_type("site/index.html", &metadata),
+            "text/html; charset=utf-8"
+        );
+        assert_eq!(content_type("site/logo.PNG", &metadata), "image/png");
+        assert_eq!(
+            content_type("site/LICENSE", &metadata),
+            "application/octet-stream"
+        );
+
+        let metadata = HashMap::from([(CONTENT_TYPE_KEY.to_string(), "text/x-rust".to_string())]);
+        assert_eq!(content_type("site/main.html", &metadata), "text/x-rust");
+    }
+
+    #[test]
+    fn test_encode_path() {
+        assert_eq!(encode_path("docs/guide-v1.2_~x"), "docs/guide-v1.2_~x");
+        assert_eq!(encode_path("my docs/%20"), "my%20docs/%2520");
+        assert_eq!(encode_path("a?b#c\r\n"), "a%3Fb%23c%0D%0A");
+        assert_eq!(encode_path("café"), "caf%C3%A9");
+    }
+
+    #[test]
+    fn test_matches_etag() {
+        let etag = "\"bafybeig\"";
+        assert!(matches_etag("\"bafybeig\"", etag));
+        assert!(matches_etag("W/\"bafybeig\"", etag));
+        assert!(matches_etag("*", etag));
+        assert!(!matches_etag("\"bafybeih\"", etag));
+    }
+}
@@ -14,7 +14,11 @@ use adm_provider::{
     tx::BroadcastMode as SDKBroadcastMode,
     util::{parse_address, parse_query_height, parse_token_amount_from_atto},
 };
-use adm_sdk::{network::Network as SdkNetwork, TxParams};
+use adm_sdk::{
+    network::Network as SdkNetwork,
+    progress::{Progress, TerminalProgress},
+    TxParams,
+};
 use adm_signer::{key::parse_secret_key, AccountKind, Signer, SubnetID, Wallet};
 
 use crate::account::{handle_account, AccountArgs};
@@ -209,6 +213,15 @@ fn get_rpc_url(cli: &Cli) -> anyhow::Result<Url> {
     Ok(cli.rpc_url.clone().unwrap_or(cli.network.get().rpc_url()?))
 }
 
+/// Returns terminal progress reporting unless the output is quiet.
+fn get_progress(cli: &Cli) -> Progress {
+    if cli.quiet {
+        Progress::default()
+    } else {
+        Progress::new(TerminalProgress::new())
+    }
+}
+
 /// Print serializable to stdout as pretty formatted JSON.
 fn print_json<T: Serialize>(value: &T) -> anyhow::Result<()> {
     let json = serde_json::to_string_pretty(&value)?;
@@ -44,6 +44,8 @@ pub enum TxStatus {
     Pending,
     /// The transaction has been committed to a finalized block.
     Committed,
+    /// No transaction was sent because the state already matched the request.
+    Unchanged,
 }
 
 /// The receipt of a transaction.
@@ -86,6 +88,18 @@ impl<D> TxReceipt<D> {
             data,
         }
     }
+
+    /// Create a new receipt with status unchanged.
+    /// The height is the block height at which the state was found to match.
+    pub fn unchanged(height: Height, data: Option<D>) -> Self {
+        TxReceipt {
+            status: TxStatus::Unchanged,
+            hash: Hash::None,
+            height: Some(height),
+            gas_used: 0,
+            data,
+        }
+    }
 }
 
 /// Provider for submitting transactions.
@@ -0,0 +1,157 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use clap::{Parser, ValueEnum};
+use fendermint_crypto::SecretKey;
+use fvm_shared::address::Address;
+use stderrlog::Timestamp;
+use tendermint_rpc::Url;
+use tokio::{net::TcpListener, sync::Mutex};
+
+use adm_provider::{
+    json_rpc::JsonRpcProvider, tx::BroadcastMode as SDKBroadcastMode, util::parse_address,
+};
+use adm_sdk::network::Network as SdkNetwork;
+use adm_signer::{key::parse_secret_key, AccountKind, SubnetID, Wallet};
+
+use crate::s3::{router, Gateway};
+
+mod s3;
+mod xml;
+
+#[derive(Clone, Debug, Parser)]
+#[command(name = "adm-s3-gateway", author, version, about, long_about = None)]
+struct Cli {
+    /// Network presets for subnet and RPC URLs.
+    #[arg(short, long, env, value_enum, default_value_t = Network::Testnet)]
+    network: Network,
+    /// The ID of the target subnet.
+    #[arg(short, long, env)]
+    subnet: Option<SubnetID>,
+    /// Node CometBFT RPC URL.
+    #[arg(long, env)]
+    rpc_url: Option<Url>,
+    /// Node Object API URL.
+    #[arg(long, env)]
+    object_api_url: Option<Url>,
+    /// Wallet private key (ECDSA, secp256k1) for signing transactions.
+    /// The gateway is read-only if no key is given.
+    #[arg(short, long, env, value_parser = parse_secret_key)]
+    private_key: Option<SecretKey>,
+    /// Socket address to listen on.
+    #[arg(short, long, env, default_value = "127.0.0.1:8014")]
+    listen: SocketAddr,
+    /// Bucket alias for an object store machine address, e.g., "photos=t2weumc7o...".
+    /// Buckets can also be addressed by machine address without an alias.
+    #[arg(long = "bucket", value_parser = parse_bucket)]
+    buckets: Vec<(String, Address)>,
+    /// Broadcast mode for write transactions.
+    #[arg(short, long, value_enum, env, default_value_t = BroadcastMode::Commit)]
+    broadcast_mode: BroadcastMode,
+    /// Logging verbosity (repeat for more verbose logging).
+    #[arg(short, long, env, action = clap::ArgAction::Count)]
+    verbosity: u8,
+    /// Silence logging.
+    #[arg(short, long, env, default_value_t = false)]
+    quiet: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum Network {
+    /// Network presets for mainnet.
+    Mainnet,
+    /// Network presets for Calibration (default pre-mainnet).
+    Testnet,
+    /// Network presets for a local three-node network.
+    Localnet,
+    /// Network presets for local development.
+    Devnet,
+}
+
+impl Network {
+    pub fn get(&self) -> SdkNetwork {
+        match self {
+            Network::Mainnet => SdkNetwork::Mainnet,
+            Network::Testnet => SdkNetwork::Testnet,
+            Network::Localnet => SdkNetwork::Localnet,
+            Network::Devnet => SdkNetwork::Devnet,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+enum BroadcastMode {
+    /// Return immediately after the transaction is broadcasted without waiting for check results.
+    Async,
+    /// Wait for the check results before returning from broadcast.
+    Sync,
+    /// Wait for the delivery results before returning from broadcast.
+    Commit,
+}
+
+impl BroadcastMode {
+    pub fn get(&self) -> SDKBroadcastMode {
+        match self {
+            BroadcastMode::Async => SDKBroadcastMode::Async,
+            BroadcastMode::Sync => SDKBroadcastMode::Sync,
+            BroadcastMode::Commit => SDKBroadcastMode::Commit,
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    stderrlog::new()
+        .module(module_path!())
+        .quiet(cli.quiet)
+        .verbosity(cli.verbosity as usize)
+        .timestamp(Timestamp::Millisecond)
+        .init()
+        .unwrap();
+
+    let network = cli.network.get();
+    network.init();
+
+    let subnet_id = cli.subnet.clone().unwrap_or(network.subnet_id()?);
+    let rpc_url = cli.rpc_url.clone().unwrap_or(network.rpc_url()?);
+    let object_api_url = cli
+        .object_api_url
+        .clone()
+        .unwrap_or(network.object_api_url()?);
+    let provider = JsonRpcProvider::new_http(rpc_url, None, Some(object_api_url))?;
+
+    let signer = match cli.private_key {
+        Some(sk) => {
+            let mut signer = Wallet::new_secp256k1(sk, AccountKind::Ethereum, subnet_id)?;
+            signer.set_sequence(None, &provider).await?;
+            Some(signer)
+        }
+        None => None,
+    };
+
+    let gateway = Gateway {
+        provider,
+        signer,
+        buckets: cli.buckets.into_iter().collect(),
+        broadcast_mode: cli.broadcast_mode.get(),
+        write_lock: Mutex::new(()),
+    };
+    let listener = TcpListener::bind(cli.listen).await?;
+    tracing::info!("Listening on {}", cli.listen);
+    axum::serve(listener, router(Arc::new(gateway))).await?;
+    Ok(())
+}
+
+/// Parse a bucket alias of the form "name=address".
+fn parse_bucket(s: &str) -> anyhow::Result<(String, Address)> {
+    let (name, address) = s
+        .split_once('=')
+        .ok_or_else(|| anyhow!("invalid bucket '{}'; expected NAME=ADDRESS", s))?;
+    Ok((name.to_string(), parse_address(address)?))
+}
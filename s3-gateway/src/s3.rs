@@ -0,0 +1,742 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_tempfile::TempFile;
+use axum::{
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use fendermint_vm_message::query::FvmQueryHeight;
+use futures::StreamExt;
+use fvm_shared::address::Address;
+use serde::Deserialize;
+use tokio::{
+    io::{AsyncSeekExt, AsyncWriteExt},
+    sync::Mutex,
+};
+use tokio_util::io::ReaderStream;
+
+use adm_provider::{
+    json_rpc::JsonRpcProvider, query::QueryProvider, tx::BroadcastMode, util::parse_address,
+};
+use adm_sdk::machine::{
+    objectstore::{
+        compute_cid, parse_range, DeleteOptions, GetOptions, ObjectHead, ObjectStore,
+        PresignOptions, QueryOptions, CONTENT_ENCODING_KEY, CONTENT_TYPE_KEY,
+    },
+    Machine,
+};
+use adm_sdk::progress::Progress;
+use adm_signer::{Signer, Wallet};
+
+use crate::xml::{self, ListBucketResult, ListEntry};
+
+/// Prefix of request headers that carry user metadata.
+const METADATA_HEADER_PREFIX: &str = "x-amz-meta-";
+/// Maximum number of keys returned by a single list request.
+const MAX_KEYS: u64 = 1000;
+/// Size of the buffer between an object download and its response body.
+const BODY_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Shared state of the gateway.
+pub(crate) struct Gateway {
+    pub provider: JsonRpcProvider,
+    /// Signer for write requests. The gateway is read-only without one.
+    pub signer: Option<Wallet>,
+    /// Object store machine addresses by bucket alias.
+    pub buckets: HashMap<String, Address>,
+    pub broadcast_mode: BroadcastMode,
+    /// Held while write transactions are built and broadcasted, so that concurrent requests
+    /// use the signer's sequence in order.
+    pub write_lock: Mutex<()>,
+}
+
+/// An error rendered as an S3 error response.
+pub(crate) struct S3Error {
+    status: StatusCode,
+    code: &'static str,
+    message: String,
+}
+
+type S3Result<T> = Result<T, S3Error>;
+
+impl S3Error {
+    fn new(status: StatusCode, code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            code,
+            message: message.into(),
+        }
+    }
+
+    fn not_implemented(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_IMPLEMENTED, "NotImplemented", message)
+    }
+}
+
+impl<E: Into<anyhow::Error>> From<E> for S3Error {
+    fn from(e: E) -> Self {
+        let e = e.into();
+        tracing::error!("{:#}", e);
+        Self::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "InternalError",
+            e.to_string(),
+        )
+    }
+}
+
+impl IntoResponse for S3Error {
+    fn into_response(self) -> Response {
+        let body = xml::error(self.code, &self.message);
+        (
+            self.status,
+            [(header::CONTENT_TYPE, "application/xml")],
+            body,
+        )
+            .into_response()
+    }
+}
+
+impl Gateway {
+    /// Returns the object store for a bucket alias or machine address.
+    fn machine(&self, bucket: &str) -> S3Result<ObjectStore> {
+        let address = match self.buckets.get(bucket) {
+            Some(address) => *address,
+            None => parse_address(bucket).map_err(|_| no_such_bucket(bucket))?,
+        };
+        Ok(ObjectStore::attach(address))
+    }
+
+    /// Returns a handle to the signer, which shares its sequence with the gateway's signer.
+    fn signer(&self) -> S3Result<Wallet> {
+        self.signer.clone().ok_or_else(|| {
+            S3Error::new(
+                StatusCode::FORBIDDEN,
+                "AccessDenied",
+                "gateway is read-only; no private key was given",
+            )
+        })
+    }
+
+    /// Re-sync the signer's sequence with the chain after a failed write.
+    ///
+    /// A transaction rejected at CheckTx doesn't consume the sequence it was signed with, so
+    /// without this every later write would be signed with a sequence the chain never reaches.
+    /// Must be called while holding the write lock.
+    async fn sync_signer(&self, signer: &mut Wallet) {
+        if let Err(e) = signer.sync_sequence(&self.provider).await {
+            tracing::error!("failed to re-sync signer sequence: {:#}", e);
+        }
+    }
+
+    /// Get an object's info, failing if it doesn't exist.
+    async fn head(&self, machine: &ObjectStore, key: &str) -> S3Result<ObjectHead> {
+        machine
+            .head(&self.provider, key, FvmQueryHeight::Committed)
+            .await?
+            .ok_or_else(|| {
+                S3Error::new(
+                    StatusCode::NOT_FOUND,
+                    "NoSuchKey",
+                    format!("object not found for key '{}'", key),
+                )
+            })
+    }
+}
+
+/// Create the gateway's router.
+///
+/// Only path-style requests are supported, i.e., `/<bucket>/<key>`.
+pub(crate) fn router(gateway: Arc<Gateway>) -> Router {
+    Router::new()
+        .route("/:bucket", get(list_objects))
+        .route("/:bucket/", get(list_objects))
+        .route(
+            "/:bucket/*key",
+            get(get_object)
+                .head(head_object)
+                .put(put_object)
+                .delete(delete_object),
+        )
+        .with_state(gateway)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ListParams {
+    list_type: Option<u8>,
+    #[serde(default)]
+    prefix: String,
+    #[serde(default)]
+    delimiter: String,
+    continuation_token: Option<String>,
+    max_keys: Option<u64>,
+}
+
+/// ListObjectsV2.
+///
+/// Continuation tokens pin the block height of the first page, so paging through a bucket
+/// sees a consistent listing.
+async fn list_objects(
+    State(gateway): State<Arc<Gateway>>,
+    Path(bucket): Path<String>,
+    Query(params): Query<ListParams>,
+) -> S3Result<Response> {
+    if params.list_type != Some(2) {
+        return Err(S3Error::not_implemented(
+            "only ListObjectsV2 (list-type=2) is supported",
+        ));
+    }
+    let machine = gateway.machine(&bucket)?;
+    let max_keys = params.max_keys.unwrap_or(MAX_KEYS).min(MAX_KEYS);
+    let cursor = match &params.continuation_token {
+        Some(token) => decode_token(token)?,
+        None => {
+            let response = gateway
+                .provider
+                .actor_state(&machine.address(), FvmQueryHeight::Committed)
+                .await?;
+            if response.value.is_none() {
+                return Err(no_such_bucket(&bucket));
+            }
+            ListCursor {
+                height: response.height.value(),
+                offset: 0,
+                marker: String::new(),
+            }
+        }
+    };
+
+    let page = list_page(
+        &gateway.provider,
+        &machine,
+        &params.prefix,
+        &params.delimiter,
+        max_keys,
+        cursor,
+    )
+    .await?;
+    let result = ListBucketResult {
+        name: &bucket,
+        prefix: &params.prefix,
+        delimiter: &params.delimiter,
+        max_keys,
+        continuation_token: params.continuation_token.as_deref(),
+        next_continuation_token: page.next.as_ref().map(encode_token),
+        contents: page.contents,
+        common_prefixes: page.common_prefixes,
+    };
+    Ok(([(header::CONTENT_TYPE, "application/xml")], result.to_xml()).into_response())
+}
+
+/// Position of a listing page, carried from page to page in the continuation token.
+#[derive(Debug, PartialEq)]
+struct ListCursor {
+    /// Block height the listing is pinned to.
+    height: u64,
+    /// Number of objects returned by earlier pages.
+    offset: u64,
+    /// Greatest key or common prefix returned by earlier pages.
+    marker: String,
+}
+
+/// A page of a listing.
+struct ListPage {
+    contents: Vec<ListEntry>,
+    common_prefixes: Vec<String>,
+    /// Cursor of the next page, if the listing may continue.
+    next: Option<ListCursor>,
+}
+
+/// Query a page of up to `max_keys` objects, starting at `cursor`.
+///
+/// The store pages over objects only and returns the common prefixes of every key it visits,
+/// so a page may hold more than `max_keys` entries in all. Prefixes that sort at or before the
+/// cursor's marker were returned by an earlier page and are dropped.
+async fn list_page(
+    provider: &impl QueryProvider,
+    machine: &ObjectStore,
+    prefix: &str,
+    delimiter: &str,
+    max_keys: u64,
+    cursor: ListCursor,
+) -> anyhow::Result<ListPage> {
+    // A limit of zero lists everything, so an empty request never reaches the store
+    if max_keys == 0 {
+        return Ok(ListPage {
+            contents: Vec::new(),
+            common_prefixes: Vec::new(),
+            next: None,
+        });
+    }
+
+    let list = machine
+        .query(
+            provider,
+            QueryOptions {
+                prefix: prefix.into(),
+                delimiter: delimiter.into(),
+                offset: cursor.offset,
+                limit: max_keys,
+                height: FvmQueryHeight::Height(cursor.height),
+            },
+        )
+        .await?;
+    let mut marker = cursor.marker;
+    let mut contents = Vec::with_capacity(list.objects.len());
+    for (key, object) in list.objects {
+        let cid = cid::Cid::try_from(object.cid.0)?;
+        contents.push(ListEntry {
+            key: String::from_utf8(key)?,
+            etag: etag(&cid.to_string()),
+            size: object.size,
+        });
+    }
+    let mut common_prefixes = Vec::new();
+    for common_prefix in list.common_prefixes {
+        let common_prefix = String::from_utf8(common_prefix)?;
+        if common_prefix > marker {
+            common_prefixes.push(common_prefix);
+        }
+    }
+    let last = contents.last().map(|entry| &entry.key);
+    if let Some(last) = last.into_iter().chain(common_prefixes.last()).max() {
+        marker = marker.max(last.clone());
+    }
+
+    // A full page may be followed by an empty one, which clients handle as the end
+    let count = contents.len() as u64;
+    let next = (count == max_keys).then(|| ListCursor {
+        height: cursor.height,
+        offset: cursor.offset + count,
+        marker,
+    });
+    Ok(ListPage {
+        contents,
+        common_prefixes,
+        next,
+    })
+}
+
+/// GetObject.
+///
+/// Objects are served as stored. Compressed objects are served with a `Content-Encoding` header.
+async fn get_object(
+    State(gateway): State<Arc<Gateway>>,
+    Path((bucket, key)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> S3Result<Response> {
+    let machine = gateway.machine(&bucket)?;
+    let head = gateway.head(&machine, &key).await?;
+    let size = content_length(&head)?;
+    let range = requested_range(&headers, size)?;
+
+    let mut response = Response::builder();
+    for (name, value) in object_headers(&head) {
+        response = response.header(name, value);
+    }
+    response = match range {
+        Some((start, end)) => response
+            .status(StatusCode::PARTIAL_CONTENT)
+            .header(header::CONTENT_LENGTH, end - start + 1)
+            .header(
+                header::CONTENT_RANGE,
+                format!("bytes {}-{}/{}", start, end, size),
+            ),
+        None => response.header(header::CONTENT_LENGTH, size),
+    };
+
+    let (reader, writer) = tokio::io::duplex(BODY_BUFFER_SIZE);
+    let options = GetOptions {
+        range: range.map(|(start, end)| format!("{}-{}", start, end)),
+        height: FvmQueryHeight::Height(head.height),
+        raw: true,
+        ..Default::default()
+    };
+    tokio::spawn(async move {
+        // The response is already underway, so errors can only end the body early
+        if let Err(e) = machine.get(&gateway.provider, &key, writer, options).await {
+            tracing::error!("failed to get object '{}': {:#}", key, e);
+        }
+    });
+    Ok(response.body(Body::from_stream(ReaderStream::new(reader)))?)
+}
+
+/// HeadObject.
+async fn head_object(
+    State(gateway): State<Arc<Gateway>>,
+    Path((bucket, key)): Path<(String, String)>,
+) -> S3Result<Response> {
+    let machine = gateway.machine(&bucket)?;
+    let head = gateway.head(&machine, &key).await?;
+    let mut response = Response::builder().header(header::CONTENT_LENGTH, content_length(&head)?);
+    for (name, value) in object_headers(&head) {
+        response = response.header(name, value);
+    }
+    Ok(response.body(Body::empty())?)
+}
+
+/// PutObject.
+///
+/// The body is buffered to a temporary file before the object is added, since adding requires
+/// the content to be read twice. Metadata is taken from `x-amz-meta-*` headers.
+async fn put_object(
+    State(gateway): State<Arc<Gateway>>,
+    Path((bucket, key)): Path<(String, String)>,
+    headers: HeaderMap,
+    body: Body,
+) -> S3Result<Response> {
+    let machine = gateway.machine(&bucket)?;
+    let mut signer = gateway.signer()?;
+    let streaming = headers
+        .get("x-amz-content-sha256")
+        .is_some_and(|value| value.as_bytes().starts_with(b"STREAMING-"));
+    if streaming {
+        return Err(S3Error::not_implemented(
+            "chunked payload signing is not supported; send unsigned or single-chunk payloads",
+        ));
+    }
+
+    let mut metadata = HashMap::new();
+    for (name, value) in &headers {
+        let Ok(value) = value.to_str() else {
+            continue;
+        };
+        if let Some(key) = name.as_str().strip_prefix(METADATA_HEADER_PREFIX) {
+            metadata.insert(key.to_string(), value.to_string());
+        } else if *name == header::CONTENT_TYPE {
+            metadata.insert(CONTENT_TYPE_KEY.to_string(), value.to_string());
+        }
+    }
+
+    let mut file = TempFile::new().await?;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        file.write_all(&chunk?).await?;
+    }
+    file.flush().await?;
+    file.rewind().await?;
+    let (cid, size) = compute_cid(&mut file).await?;
+    file.rewind().await?;
+
+    // Staging the content doesn't use the signer's sequence, so only the broadcast is serialized
+    let upload = machine.presign(
+        &signer,
+        &key,
+        cid,
+        size,
+        PresignOptions {
+            overwrite: true,
+            metadata,
+        },
+    )?;
+    machine
+        .upload_presigned(&gateway.provider, &upload, file, Progress::default())
+        .await?;
+
+    let _guard = gateway.write_lock.lock().await;
+    let result = machine
+        .add_presigned(
+            &gateway.provider,
+            &mut signer,
+            &upload,
+            gateway.broadcast_mode,
+            Default::default(),
+        )
+        .await;
+    if result.is_err() {
+        gateway.sync_signer(&mut signer).await;
+    }
+    let receipt = result?;
+
+    let mut response = Response::builder();
+    if let Some(cid) = receipt.data {
+        response = response.header(header::ETAG, etag(&cid.to_string()));
+    }
+    Ok(response.body(Body::empty())?)
+}
+
+/// DeleteObject.
+///
+/// Like S3, deleting a key that doesn't exist succeeds.
+async fn delete_object(
+    State(gateway): State<Arc<Gateway>>,
+    Path((bucket, key)): Path<(String, String)>,
+) -> S3Result<Response> {
+    let machine = gateway.machine(&bucket)?;
+    let mut signer = gateway.signer()?;
+    let exists = machine
+        .head(&gateway.provider, &key, FvmQueryHeight::Pending)
+        .await?
+        .is_some();
+    if exists {
+        let _guard = gateway.write_lock.lock().await;
+        let result = machine
+            .delete(
+                &gateway.provider,
+                &mut signer,
+                &key,
+                DeleteOptions {
+                    broadcast_mode: gateway.broadcast_mode,
+                    ..Default::default()
+                },
+            )
+            .await;
+        if result.is_err() {
+            gateway.sync_signer(&mut signer).await;
+        }
+        result?;
+    }
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+fn no_such_bucket(bucket: &str) -> S3Error {
+    S3Error::new(
+        StatusCode::NOT_FOUND,
+        "NoSuchBucket",
+        format!("bucket '{}' not found", bucket),
+    )
+}
+
+/// Returns an object's content length, failing if the object is not yet resolved.
+fn content_length(head: &ObjectHead) -> S3Result<usize> {
+    head.content_length.ok_or_else(|| {
+        S3Error::new(
+            StatusCode::CONFLICT,
+            "InvalidObjectState",
+            format!("object '{}' is not resolved", head.key),
+        )
+    })
+}
+
+/// Returns the response headers describing an object.
+fn object_headers(head: &ObjectHead) -> Vec<(HeaderName, HeaderValue)> {
+    let mut headers = vec![(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"))];
+    if let Ok(cid) = cid::Cid::try_from(head.object.cid.0.as_slice()) {
+        if let Ok(value) = HeaderValue::from_str(&etag(&cid.to_string())) {
+            headers.push((header::ETAG, value));
+        }
+    }
+    let content_type = head
+        .object
+        .metadata
+        .get(CONTENT_TYPE_KEY)
+        .and_then(|value| HeaderValue::from_str(value).ok())
+        .unwrap_or(HeaderValue::from_static("application/octet-stream"));
+    headers.push((header::CONTENT_TYPE, content_type));
+    for (key, value) in &head.object.metadata {
+        let name = match key.as_str() {
+            CONTENT_TYPE_KEY => continue,
+            CONTENT_ENCODING_KEY => Ok(header::CONTENT_ENCODING),
+            _ => HeaderName::try_from(format!("{}{}", METADATA_HEADER_PREFIX, key)),
+        };
+        if let (Ok(name), Ok(value)) = (name, HeaderValue::from_str(value)) {
+            headers.push((name, value));
+        }
+    }
+    headers
+}
+
+/// Returns the inclusive byte range requested by a `Range` header, if any.
+fn requested_range(headers: &HeaderMap, size: usize) -> S3Result<Option<(usize, usize)>> {
+    let Some(value) = headers.get(header::RANGE) else {
+        return Ok(None);
+    };
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| parse_range(value, size))
+        .map(Some)
+        .ok_or_else(|| {
+            S3Error::new(
+                StatusCode::RANGE_NOT_SATISFIABLE,
+                "InvalidRange",
+                "the requested range is not satisfiable",
+            )
+        })
+}
+
+fn etag(cid: &str) -> String {
+    format!("\"{}\"", cid)
+}
+
+fn encode_token(cursor: &ListCursor) -> String {
+    URL_SAFE_NO_PAD.encode(format!(
+        "{}:{}:{}",
+        cursor.height, cursor.offset, cursor.marker
+    ))
+}
+
+fn decode_token(token: &str) -> S3Result<ListCursor> {
+    let invalid = || {
+        S3Error::new(
+            StatusCode::BAD_REQUEST,
+            "InvalidArgument",
+            "the continuation token provided is incorrect",
+        )
+    };
+    let decoded = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+    let decoded = String::from_utf8(decoded).map_err(|_| invalid())?;
+    let mut parts = decoded.splitn(3, ':');
+    let mut next = || parts.next().ok_or_else(invalid);
+    let height = next()?.parse().map_err(|_| invalid())?;
+    let offset = next()?.parse().map_err(|_| invalid())?;
+    let marker = next()?.to_string();
+    Ok(ListCursor {
+        height,
+        offset,
+        marker,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use adm_sdk::machine::objectstore::mock::{object, MockQueryProvider};
+
+    use super::*;
+
+    fn cursor(height: u64, offset: u64, marker: &str) -> ListCursor {
+        ListCursor {
+            height,
+            offset,
+            marker: marker.into(),
+        }
+    }
+
+    /// Page through a listing, returning the keys and common prefixes of each page.
+    async fn pages(
+        provider: &MockQueryProvider,
+        delimiter: &str,
+        max_keys: u64,
+    ) -> Vec<(Vec<String>, Vec<String>)> {
+        let machine = ObjectStore::attach(Address::new_id(1001));
+        let mut pages = Vec::new();
+        let mut next = Some(cursor(1, 0, ""));
+        while let Some(cursor) = next {
+            // Each page resumes from its encoded token, as a client would
+            let cursor = decode_token(&encode_token(&cursor)).ok().unwrap();
+            let page = list_page(provider, &machine, "", delimiter, max_keys, cursor)
+                .await
+                .unwrap();
+            let keys = page.contents.into_iter().map(|entry| entry.key).collect();
+            pages.push((keys, page.common_prefixes));
+            next = page.next;
+        }
+        pages
+    }
+
+    fn mock_provider(keys: &[&str]) -> MockQueryProvider {
+        let objects: Vec<_> = keys.iter().map(|key| (*key, object(b"x", &[]))).collect();
+        let mut provider = MockQueryProvider::new(10);
+        provider.set(1, &objects);
+        provider
+    }
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn test_token() {
+        for cursor in [cursor(7, 0, ""), cursor(42, 1000, "a:b/c:")] {
+            assert_eq!(decode_token(&encode_token(&cursor)).ok(), Some(cursor));
+        }
+
+        let invalid: [&str; 5] = [
+            "not base64!",
+            &URL_SAFE_NO_PAD.encode([0xff, 0xfe]),
+            &URL_SAFE_NO_PAD.encode("42:1000"),
+            &URL_SAFE_NO_PAD.encode("x:1000:"),
+            &URL_SAFE_NO_PAD.encode("42:-1:"),
+        ];
+        for token in invalid {
+            let err = decode_token(token).err().unwrap();
+            assert_eq!(err.status, StatusCode::BAD_REQUEST);
+            assert_eq!(err.code, "InvalidArgument");
+        }
+    }
+
+    #[test]
+    fn test_requested_range() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(requested_range(&headers, 100).ok(), Some(None));
+
+        headers.insert(header::RANGE, HeaderValue::from_static("bytes=10-19"));
+        assert_eq!(requested_range(&headers, 100).ok(), Some(Some((10, 19))));
+        headers.insert(header::RANGE, HeaderValue::from_static("bytes=-10"));
+        assert_eq!(requested_range(&headers, 100).ok(), Some(Some((90, 99))));
+
+        for value in ["bytes=100-", "bytes=0-9,20-29", "items=0-9"] {
+            headers.insert(header::RANGE, HeaderValue::from_static(value));
+            let err = requested_range(&headers, 100).err().unwrap();
+            assert_eq!(err.status, StatusCode::RANGE_NOT_SATISFIABLE);
+            assert_eq!(err.code, "InvalidRange");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_page() {
+        let provider = mock_provider(&["a", "b", "c", "d", "e"]);
+        assert_eq!(
+            pages(&provider, "", 2).await,
+            vec![
+                (strings(&["a", "b"]), vec![]),
+                (strings(&["c", "d"]), vec![]),
+                (strings(&["e"]), vec![]),
+            ]
+        );
+        // A full last page is followed by an empty one
+        assert_eq!(
+            pages(&provider, "", 5).await,
+            vec![
+                (strings(&["a", "b", "c", "d", "e"]), vec![]),
+                (vec![], vec![])
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_page_delimiter() {
+        // Prefixes don't count toward a page's objects, so no object is skipped
+        let provider = mock_provider(&["a/1", "b", "c", "d"]);
+        assert_eq!(
+            pages(&provider, "/", 2).await,
+            vec![
+                (strings(&["b", "c"]), strings(&["a/"])),
+                (strings(&["d"]), vec![]),
+            ]
+        );
+
+        // A prefix returned after a page's last object isn't returned again
+        let provider = mock_provider(&["b", "c", "c0/1", "c0/2", "d", "e/1"]);
+        assert_eq!(
+            pages(&provider, "/", 2).await,
+            vec![
+                (strings(&["b", "c"]), strings(&["c0/"])),
+                (strings(&["d"]), strings(&["e/"])),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_page_zero_keys() {
+        let provider = mock_provider(&["a", "b"]);
+        let machine = ObjectStore::attach(Address::new_id(1001));
+        let page = list_page(&provider, &machine, "", "/", 0, cursor(1, 0, ""))
+            .await
+            .unwrap();
+        assert!(page.contents.is_empty());
+        assert!(page.common_prefixes.is_empty());
+        assert_eq!(page.next, None);
+        assert!(provider.queries.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,152 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt::Write;
+
+const XML_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>"#;
+const S3_NAMESPACE: &str = "http://s3.amazonaws.com/doc/2006-03-01/";
+
+/// Objects don't record when they were written, so listings report the Unix epoch.
+const LAST_MODIFIED: &str = "1970-01-01T00:00:00.000Z";
+
+/// A ListObjectsV2 response.
+pub(crate) struct ListBucketResult<'a> {
+    pub name: &'a str,
+    pub prefix: &'a str,
+    pub delimiter: &'a str,
+    pub max_keys: u64,
+    pub continuation_token: Option<&'a str>,
+    pub next_continuation_token: Option<String>,
+    pub contents: Vec<ListEntry>,
+    pub common_prefixes: Vec<String>,
+}
+
+/// An object in a [`ListBucketResult`].
+pub(crate) struct ListEntry {
+    pub key: String,
+    pub etag: String,
+    pub size: usize,
+}
+
+impl ListBucketResult<'_> {
+    /// Render the response body.
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::new();
+        xml.push_str(XML_HEADER);
+        let _ = write!(xml, r#"<ListBucketResult xmlns="{}">"#, S3_NAMESPACE);
+        element(&mut xml, "Name", self.name);
+        element(&mut xml, "Prefix", self.prefix);
+        if !self.delimiter.is_empty() {
+            element(&mut xml, "Delimiter", self.delimiter);
+        }
+        element(&mut xml, "MaxKeys", &self.max_keys.to_string());
+        let key_count = self.contents.len() + self.common_prefixes.len();
+        element(&mut xml, "KeyCount", &key_count.to_string());
+        let truncated = self.next_continuation_token.is_some();
+        element(&mut xml, "IsTruncated", &truncated.to_string());
+        if let Some(token) = self.continuation_token {
+            element(&mut xml, "ContinuationToken", token);
+        }
+        if let Some(token) = &self.next_continuation_token {
+            element(&mut xml, "NextContinuationToken", token);
+        }
+        for entry in &self.contents {
+            xml.push_str("<Contents>");
+            element(&mut xml, "Key", &entry.key);
+            element(&mut xml, "LastModified", LAST_MODIFIED);
+            element(&mut xml, "ETag", &entry.etag);
+            element(&mut xml, "Size", &entry.size.to_string());
+            element(&mut xml, "StorageClass", "STANDARD");
+            xml.push_str("</Contents>");
+        }
+        for prefix in &self.common_prefixes {
+            xml.push_str("<CommonPrefixes>");
+            element(&mut xml, "Prefix", prefix);
+            xml.push_str("</CommonPrefixes>");
+        }
+        xml.push_str("</ListBucketResult>");
+        xml
+    }
+}
+
+/// Render an S3 error response body.
+pub(crate) fn error(code: &str, message: &str) -> String {
+    let mut xml = String::new();
+    xml.push_str(XML_HEADER);
+    xml.push_str("<Error>");
+    element(&mut xml, "Code", code);
+    element(&mut xml, "Message", message);
+    xml.push_str("</Error>");
+    xml
+}
+
+fn element(xml: &mut String, name: &str, value: &str) {
+    let _ = write!(xml, "<{}>{}</{}>", name, escape(value), name);
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a/b.txt"), "a/b.txt");
+        assert_eq!(
+            escape(r#"<a href="x">Tom & Jerry's</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; Jerry&apos;s&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_list_bucket_result() {
+        let result = ListBucketResult {
+            name: "bucket",
+            prefix: "a&b/",
+            delimiter: "/",
+            max_keys: 2,
+            continuation_token: None,
+            next_continuation_token: Some("token".into()),
+            contents: vec![ListEntry {
+                key: "a&b/<c>".into(),
+                etag: "\"cid\"".into(),
+                size: 3,
+            }],
+            common_prefixes: vec!["a&b/d/".into()],
+        };
+        let xml = result.to_xml();
+        assert!(xml.contains("<Prefix>a&amp;b/</Prefix>"));
+        assert!(xml.contains("<Key>a&amp;b/&lt;c&gt;</Key>"));
+        assert!(xml.contains("<ETag>&quot;cid&quot;</ETag>"));
+        assert!(xml.contains("<CommonPrefixes><Prefix>a&amp;b/d/</Prefix></CommonPrefixes>"));
+        assert!(xml.contains("<KeyCount>2</KeyCount>"));
+        assert!(xml.contains("<IsTruncated>true</IsTruncated>"));
+        assert!(xml.contains("<NextContinuationToken>token</NextContinuationToken>"));
+        assert!(!xml.contains("<ContinuationToken>"));
+    }
+
+    #[test]
+    fn test_error() {
+        assert_eq!(
+            error("NoSuchKey", "key 'a<b' not found"),
+            format!(
+                "{}<Error><Code>NoSuchKey</Code><Message>key &apos;a&lt;b&apos; not found</Message></Error>",
+                XML_HEADER
+            )
+        );
+    }
+}
@@ -1,11 +1,19 @@
 // Copyright 2024 ADM Contributors
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::{cmp::min, collections::HashMap};
+use std::{
+    cmp::min,
+    collections::{HashMap, HashSet},
+    fmt,
+    io::SeekFrom,
+    path::{Path, PathBuf},
+    pin::{pin, Pin},
+    time::{Duration, SystemTime},
+};
 
-use anyhow::anyhow;
+use anyhow::{anyhow, Context};
+use async_tempfile::TempFile;
 use async_trait::async_trait;
-use base64::{engine::general_purpose, Engine};
 use bytes::Bytes;
 use fendermint_actor_machine::WriteAccess;
 use fendermint_actor_objectstore::{
@@ -15,34 +23,62 @@ use fendermint_actor_objectstore::{
 };
 use fendermint_vm_actor_interface::adm::Kind;
 use fendermint_vm_message::{query::FvmQueryHeight, signed::Object as MessageObject};
+use futures::{stream, Stream, StreamExt};
 use fvm_ipld_encoding::RawBytes;
 use fvm_shared::address::Address;
-use indicatif::HumanDuration;
+use serde::Serialize;
 use tendermint::abci::response::DeliverTx;
 use tendermint_rpc::Client;
 use tokio::{
     io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt},
-    time::Instant,
+    time::{sleep, Instant},
 };
-use tokio_stream::StreamExt;
 use tokio_util::io::ReaderStream;
-use unixfs_v1::file::adder::{Chunker, FileAdder};
 
 use adm_provider::{
-    message::{local_message, object_upload_message, GasParams},
+    message::{local_message, GasParams},
     object::ObjectProvider,
-    query::QueryProvider,
+    query::{QueryProvider, QueryResponse},
     response::{decode_bytes, decode_cid, Cid},
     tx::{BroadcastMode, TxReceipt},
     Provider,
 };
 use adm_signer::Signer;
 
-use crate::progress::{new_message_bar, new_multi_bar, SPARKLE};
-use crate::{
-    machine::{deploy_machine, DeployTxReceipt, Machine},
-    progress::new_progress_bar,
-};
+use crate::machine::{deploy_machine, DeployTxReceipt, Machine};
+use crate::progress::{HumanDuration, Progress, ProgressEvent};
+
+use crypt::{is_encrypted, Decryptor, Encryptor};
+use journal::{fingerprint, DownloadJournal, Journal, UploadJournal};
+use unixfs::{CidBuilder, CHUNK_SIZE};
+
+pub use audit::{DiffEntry, ModifiedEntry, ObjectState, ObjectVersion, StoreDiff};
+pub use car::{ExportOptions, ExportSummary, ImportOptions, ImportSummary};
+pub use compression::{Compression, CONTENT_ENCODING_KEY};
+pub use crypt::EncryptionKey;
+pub use presign::{PresignOptions, PresignedUpload};
+pub use sync::{SyncDownOptions, SyncOptions, SyncSummary};
+
+mod audit;
+mod car;
+mod compression;
+mod crypt;
+mod journal;
+#[cfg(any(test, feature = "mock"))]
+pub mod mock;
+mod presign;
+mod sync;
+mod unixfs;
+
+/// Metadata key holding an object's content type.
+pub const CONTENT_TYPE_KEY: &str = "content-type";
+
+/// Size of each range requested by parallel downloads.
+const DOWNLOAD_PART_SIZE: usize = 8 * 1024 * 1024;
+
+/// Initial and maximum delays between object resolution polls.
+const RESOLVE_POLL_MIN: Duration = Duration::from_millis(500);
+const RESOLVE_POLL_MAX: Duration = Duration::from_secs(8);
 
 /// Object add options.
 #[derive(Clone, Default, Debug)]
@@ -53,10 +89,38 @@ pub struct AddOptions {
     pub broadcast_mode: BroadcastMode,
     /// Gas params for the transaction.
     pub gas_params: GasParams,
-    /// Whether to show progress-related output (useful for command-line interfaces).
-    pub show_progress: bool,
+    /// Progress reporting for the operation.
+    pub progress: Progress,
     /// Metadata to add to the object.
     pub metadata: HashMap<String, String>,
+    /// Path to a local journal used to retry an interrupted add without repeating finished steps.
+    /// If the journal describes the same object and input, hashing and an upload the Object API
+    /// acknowledged are skipped. Uploads are not resumed part-way, so one that was cut off is
+    /// sent again in full.
+    /// The journal is removed once the transaction is broadcasted.
+    pub journal: Option<PathBuf>,
+    /// Modification time of the input, used with [`AddOptions::journal`] to detect an input
+    /// that changed between attempts.
+    pub source_modified: Option<SystemTime>,
+    /// Wait up to the given timeout for the object to be resolved by validators.
+    pub wait_resolved: Option<Duration>,
+    /// Encrypt the object on the client before it's hashed and uploaded.
+    /// The cipher and key ID are recorded in the object's metadata.
+    /// Encrypted adds cannot be journaled, so [`AddOptions::journal`] must be unset.
+    pub encryption_key: Option<EncryptionKey>,
+    /// Compress the object before it's uploaded.
+    /// The compression is recorded in the object's `content-encoding` metadata.
+    pub compression: Option<Compression>,
+    /// Skip the upload and transaction if the key already holds an object with the same CID
+    /// and metadata. The returned receipt has status [`adm_provider::tx::TxStatus::Unchanged`].
+    /// Encrypted objects never match, since their ciphertext is not reproducible.
+    pub skip_unchanged: bool,
+    /// Only add the object if the key currently holds an object with this CID.
+    /// The precondition is checked against pending state right before broadcasting, and a
+    /// mismatch returns a [`ConflictError`]. Implies [`AddOptions::overwrite`].
+    /// The check is advisory, not a compare-and-swap: a write that lands between the check
+    /// and the transaction is still overwritten.
+    pub if_match: Option<Cid>,
 }
 
 /// Object delete options.
@@ -66,10 +130,86 @@ pub struct DeleteOptions {
     pub broadcast_mode: BroadcastMode,
     /// Gas params for the transaction.
     pub gas_params: GasParams,
+    /// Only delete the object if the key currently holds an object with this CID.
+    /// The precondition is checked against pending state right before broadcasting, and a
+    /// mismatch returns a [`ConflictError`].
+    /// The check is advisory, not a compare-and-swap: a write that lands between the check
+    /// and the transaction is still deleted.
+    pub if_match: Option<Cid>,
 }
 
-/// Object get options.
+/// Options for deleting every object under a prefix.
 #[derive(Clone, Default, Debug)]
+pub struct DeletePrefixOptions {
+    /// Query block height used to list the objects to delete.
+    pub height: FvmQueryHeight,
+    /// Only list the objects that would be deleted without deleting them.
+    pub dry_run: bool,
+    /// Broadcast mode for the transactions.
+    /// Transactions are sent in order without waiting on each other beyond what the mode
+    /// requires, so `Sync` and `Async` pipeline deletes across blocks.
+    /// With `Async`, nothing is checked, so keys are reported as pending instead of deleted.
+    pub broadcast_mode: BroadcastMode,
+    /// Gas params for the transactions.
+    pub gas_params: GasParams,
+    /// Progress reporting for the operation.
+    pub progress: Progress,
+}
+
+/// Summary of a prefix delete.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DeletePrefixSummary {
+    /// Keys that were deleted, or that would be deleted in a dry run.
+    pub deleted: Vec<String>,
+    /// Keys whose deletes were broadcast with [`BroadcastMode::Async`], and may still fail.
+    pub pending: Vec<String>,
+    /// Keys that could not be deleted.
+    pub failed: Vec<DeleteFailure>,
+}
+
+/// A key that could not be deleted.
+#[derive(Clone, Debug, Serialize)]
+pub struct DeleteFailure {
+    /// Object key.
+    pub key: String,
+    /// Error returned for the key.
+    pub error: String,
+}
+
+/// Error returned when an `if_match` precondition fails.
+///
+/// The error is wrapped in an [`anyhow::Error`] and can be recovered with `downcast_ref`.
+#[derive(Clone, Debug)]
+pub struct ConflictError {
+    /// Object key.
+    pub key: String,
+    /// CID the key was expected to hold.
+    pub expected: Cid,
+    /// CID the key actually holds, if any.
+    pub actual: Option<Cid>,
+}
+
+impl fmt::Display for ConflictError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.actual {
+            Some(actual) => write!(
+                f,
+                "conflict for key '{}'; expected cid {} but found {}",
+                self.key, self.expected, actual
+            ),
+            None => write!(
+                f,
+                "conflict for key '{}'; expected cid {} but key does not exist",
+                self.key, self.expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConflictError {}
+
+/// Object get options.
+#[derive(Clone, Debug)]
 pub struct GetOptions {
     /// Optional range of bytes to get from the object.
     /// Format: "start-end" (inclusive).
@@ -78,9 +218,50 @@ pub struct GetOptions {
     /// `<https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Range>`
     pub range: Option<String>,
     /// Query block height.
+    /// The object's content is downloaded at the height its info was queried at.
     pub height: FvmQueryHeight,
-    /// Whether to show progress-related output (useful for command-line interfaces).
-    pub show_progress: bool,
+    /// Progress reporting for the operation.
+    pub progress: Progress,
+    /// Whether to verify the downloaded bytes against the object's CID (default: true).
+    /// Ranged gets cannot be verified and are skipped with a warning.
+    pub verify: bool,
+    /// Number of concurrent range requests used to download the object (default: 1).
+    /// Parts are reassembled in order, so any writer can be used.
+    /// Ignored for ranged gets.
+    pub parallel: usize,
+    /// Key used to decrypt objects that were encrypted on the client.
+    /// Encrypted objects cannot be fetched by range.
+    /// Plaintext is written segment by segment as each one authenticates, so the writer may
+    /// receive data before a truncated or tampered payload fails on its final segment.
+    pub encryption_key: Option<EncryptionKey>,
+    /// Write the bytes as stored, without decompressing or decrypting them.
+    /// Compressed objects can only be fetched by range as raw bytes.
+    pub raw: bool,
+}
+
+impl Default for GetOptions {
+    fn default() -> Self {
+        GetOptions {
+            range: Default::default(),
+            height: Default::default(),
+            progress: Default::default(),
+            verify: true,
+            parallel: 1,
+            encryption_key: None,
+            raw: false,
+        }
+    }
+}
+
+/// Object download options.
+#[derive(Clone, Default, Debug)]
+pub struct DownloadOptions {
+    /// Query block height.
+    /// Resumed downloads stay pinned to the height of the first attempt, and fail if a
+    /// different block height is given.
+    pub height: FvmQueryHeight,
+    /// Progress reporting for the operation.
+    pub progress: Progress,
 }
 
 /// Object query options.
@@ -110,6 +291,43 @@ impl Default for QueryOptions {
     }
 }
 
+/// Object info returned by [`ObjectStore::head`].
+#[derive(Clone, Debug)]
+pub struct ObjectHead {
+    /// Object key.
+    pub key: String,
+    /// Object as stored in the machine.
+    pub object: Object,
+    /// Content length reported by the Object API, if the object is resolved.
+    pub content_length: Option<usize>,
+    /// Block height at which the object was queried.
+    pub height: u64,
+}
+
+/// Result of comparing local content with an object, returned by [`ObjectStore::verify`].
+#[derive(Clone, Debug, Serialize)]
+pub struct Verification {
+    /// Object key.
+    pub key: String,
+    /// CID computed from the local content.
+    pub local_cid: Cid,
+    /// CID of the object in the object store.
+    pub remote_cid: Cid,
+    /// Whether the local content matches the object.
+    pub matches: bool,
+    /// Block height at which the object was queried.
+    pub height: u64,
+}
+
+/// An entry yielded by [`ObjectStore::query_stream`].
+#[derive(Clone, Debug)]
+pub enum QueryItem {
+    /// An object and its key.
+    Object(String, Object),
+    /// A key prefix shared by objects below the delimiter.
+    CommonPrefix(String),
+}
+
 /// A machine for S3-like object storage.
 pub struct ObjectStore {
     address: Address,
@@ -150,7 +368,68 @@ impl Machine for ObjectStore {
 
 impl ObjectStore {
     /// Add an object into the object store.
+    ///
+    /// If [`AddOptions::compression`] or [`AddOptions::encryption_key`] is set, the input is
+    /// compressed and then encrypted to a temporary file first, so that the object's CID is
+    /// generated over the stored bytes.
     pub async fn add<C, R>(
+        &self,
+        provider: &impl Provider<C>,
+        signer: &mut impl Signer,
+        key: &str,
+        mut reader: R,
+        mut options: AddOptions,
+    ) -> anyhow::Result<TxReceipt<Cid>>
+    where
+        C: Client + Send + Sync,
+        R: AsyncRead + AsyncSeek + Unpin + Send + 'static,
+    {
+        let encryption_key = options.encryption_key.take();
+        if encryption_key.is_none() && options.compression.is_none() {
+            return self
+                .add_seekable(provider, signer, key, reader, options)
+                .await;
+        }
+        if encryption_key.is_some() && options.journal.is_some() {
+            return Err(anyhow!("cannot journal an encrypted add"));
+        }
+
+        let progress = &options.progress;
+        progress.step(0, 3);
+        progress.status("Encoding input...");
+        let mut source: Pin<Box<dyn AsyncRead + Send + '_>> = match options.compression {
+            Some(compression) => {
+                options.metadata.extend(compression.metadata());
+                compression.encoder(&mut reader)
+            }
+            None => Box::pin(&mut reader),
+        };
+        let mut encryptor = encryption_key.as_ref().map(Encryptor::new);
+        let mut file = TempFile::new().await?;
+        let mut buffer = vec![0; CHUNK_SIZE];
+        loop {
+            let n = source.read(&mut buffer).await?;
+            if n == 0 {
+                break;
+            }
+            match encryptor.as_mut() {
+                Some(encryptor) => file.write_all(&encryptor.push(&buffer[..n])?).await?,
+                None => file.write_all(&buffer[..n]).await?,
+            }
+        }
+        if let Some(encryptor) = encryptor {
+            options.metadata.extend(encryptor.metadata());
+            file.write_all(&encryptor.finish()?).await?;
+        }
+        file.flush().await?;
+        file.rewind().await?;
+
+        self.add_seekable(provider, signer, key, file, options)
+            .await
+    }
+
+    /// Add an object from a seekable reader, as is.
+    async fn add_seekable<C, R>(
         &self,
         provider: &impl Provider<C>,
         signer: &mut impl Signer,
@@ -163,84 +442,201 @@ impl ObjectStore {
         R: AsyncRead + AsyncSeek + Unpin + Send + 'static,
     {
         let started = Instant::now();
-        let bars = new_multi_bar(!options.show_progress);
-        let msg_bar = bars.add(new_message_bar());
-        // Generate object Cid
-        // We do this here to avoid moving the reader
-        let chunk_size = 1024 * 1024; // size-1048576
-        let adder = FileAdder::builder()
-            .with_chunker(Chunker::Size(chunk_size))
-            .build();
-        let buffer = vec![0; chunk_size];
-        let mut reader_size: usize = 0;
-        let mut object_size: usize = 0;
-
-        msg_bar.set_prefix("[1/3]");
-        let chunk = Cid::from(cid::Cid::default());
-        let object_cid = generate_cid(
-            &mut reader,
-            buffer,
-            &mut reader_size,
-            adder,
-            chunk,
-            &msg_bar,
-            &mut object_size,
-        )
-        .await?;
+        let progress = &options.progress;
+        // An if-match add replaces the object, so both the upload and the transaction overwrite
+        let overwrite = options.overwrite || options.if_match.is_some();
 
-        // Rewind and stream for uploading
-        msg_bar.set_prefix("[2/3]");
-        msg_bar.set_message(format!("Uploading {} to network...", object_cid));
-        let pro_bar = bars.add(new_progress_bar(reader_size));
+        // Pick up an existing journal if it describes this object and input
+        let source_size = reader.seek(SeekFrom::End(0)).await?;
         reader.rewind().await?;
-        let mut stream = ReaderStream::new(reader);
-        let async_stream = async_stream::stream! {
-            let mut progress: usize = 0;
-            while let Some(chunk) = stream.next().await {
-                if let Ok(chunk) = &chunk {
-                    progress = min(progress + chunk.len(), reader_size);
-                    pro_bar.set_position(progress as u64);
-                }
-                yield chunk;
+        let mut journal = match &options.journal {
+            Some(path) => {
+                let fresh = UploadJournal::new(
+                    self.address,
+                    key,
+                    source_size,
+                    options.source_modified,
+                    fingerprint(&mut reader, source_size).await?,
+                );
+                UploadJournal::load(path)
+                    .await?
+                    .filter(|j| j.matches(&fresh))
+                    .unwrap_or(fresh)
             }
-            pro_bar.finish_and_clear();
+            None => UploadJournal::new(self.address, key, source_size, None, String::new()),
         };
 
-        // Upload Object to Object API
-        let response_cid = self
-            .upload(
-                provider,
-                signer,
-                key,
-                async_stream,
-                object_cid,
-                object_size,
-                options.metadata.clone(),
-                options.overwrite,
-            )
-            .await?;
+        progress.step(1, 3);
+        let (object_cid, object_size) = if let Some(cid) = journal.cid {
+            progress.status(format!("Continuing journaled add of {}...", cid));
+            (cid, journal.object_size)
+        } else {
+            // Generate object Cid
+            // We do this here to avoid moving the reader
+            let (object_cid, object_size) = generate_cid(&mut reader, progress).await?;
+
+            journal.cid = Some(object_cid);
+            journal.object_size = object_size;
+            if let Some(path) = &options.journal {
+                journal.save(path).await?;
+            }
+            (object_cid, object_size)
+        };
 
-        // Verify uploaded CID with locally computed CID
-        if response_cid != object_cid {
-            return Err(anyhow!("cannot verify object; cid does not match remote"));
+        // Skip the upload and transaction if the key already holds the same object
+        if options.skip_unchanged {
+            let response = self
+                .get_object(provider, key, FvmQueryHeight::Committed)
+                .await?;
+            if let Some(object) = response.value {
+                let unchanged = Cid::from(cid::Cid::try_from(object.cid.0)?) == object_cid
+                    && object.metadata == options.metadata;
+                if unchanged {
+                    if let Some(path) = &options.journal {
+                        UploadJournal::remove(path).await?;
+                    }
+                    progress.finished(format!(
+                        "Object unchanged (cid={}; size={})",
+                        object_cid, object_size
+                    ));
+                    return Ok(TxReceipt::unchanged(response.height, Some(object_cid)));
+                }
+            }
+        }
+
+        progress.step(2, 3);
+        if journal.is_uploaded() {
+            progress.status(format!("Object {} already uploaded", object_cid));
+        } else {
+            // Rewind and stream for uploading
+            let reader_size = source_size as usize;
+            progress.report(ProgressEvent::UploadStarted {
+                cid: object_cid,
+                size: reader_size,
+            });
+            reader.rewind().await?;
+            let async_stream = upload_progress_stream(reader, reader_size, progress.clone());
+
+            // Upload Object to Object API
+            let response_cid = self
+                .upload(
+                    provider,
+                    signer,
+                    key,
+                    async_stream,
+                    object_cid,
+                    object_size,
+                    options.metadata.clone(),
+                    overwrite,
+                )
+                .await?;
+
+            // Verify uploaded CID with locally computed CID
+            if response_cid != object_cid {
+                // Start over next time, since the journaled CID can't be trusted
+                if let Some(path) = &options.journal {
+                    UploadJournal::remove(path).await?;
+                }
+                return Err(anyhow!("cannot verify object; cid does not match remote"));
+            }
+
+            // The Object API acknowledges the object as a whole
+            journal.uploaded = true;
+            if let Some(path) = &options.journal {
+                journal.save(path).await?;
+            }
         }
 
         // Broadcast transaction with Object's CID
-        msg_bar.set_prefix("[3/3]");
-        msg_bar.set_message("Broadcasting transaction...");
+        progress.step(3, 3);
+        progress.report(ProgressEvent::Broadcasting);
+        if let Some(expected) = options.if_match {
+            self.check_match(provider, key, expected).await?;
+        }
         let params = AddParams {
             key: key.into(),
             cid: object_cid.0,
-            overwrite: options.overwrite,
+            overwrite,
             metadata: options.metadata,
             size: object_size,
         };
-        let serialized_params = RawBytes::serialize(params.clone())?;
+        let tx = self
+            .broadcast_add(
+                provider,
+                signer,
+                params,
+                options.broadcast_mode,
+                options.gas_params,
+            )
+            .await?;
+        if let Some(path) = &options.journal {
+            UploadJournal::remove(path).await?;
+        }
+        if let Some(timeout) = options.wait_resolved {
+            progress.status("Waiting for object to resolve...");
+            self.poll_resolved(provider, key, Some(object_cid), timeout)
+                .await?;
+        }
+        progress.finished(format!(
+            "Added object in {} (cid={}; size={})",
+            HumanDuration(started.elapsed()),
+            object_cid,
+            object_size
+        ));
+        Ok(tx)
+    }
+
+    /// Add an object into the object store from a reader that can only be read once.
+    ///
+    /// The reader is buffered to a temporary file so that sources like stdin, sockets, and
+    /// decompressors can be added without seeking.
+    /// Adds from a one-shot reader cannot be journaled, so [`AddOptions::journal`] must be unset.
+    pub async fn add_reader<C, R>(
+        &self,
+        provider: &impl Provider<C>,
+        signer: &mut impl Signer,
+        key: &str,
+        mut reader: R,
+        options: AddOptions,
+    ) -> anyhow::Result<TxReceipt<Cid>>
+    where
+        C: Client + Send + Sync,
+        R: AsyncRead + Unpin + Send + 'static,
+    {
+        if options.journal.is_some() {
+            return Err(anyhow!("cannot journal an add from a non-seekable reader"));
+        }
+
+        let progress = &options.progress;
+        progress.step(0, 3);
+        progress.status("Buffering input...");
+        let mut file = TempFile::new().await?;
+        let buffered = tokio::io::copy(&mut reader, &mut file).await?;
+        file.flush().await?;
+        file.rewind().await?;
+        progress.status(format!("Buffered {} bytes", buffered));
+
+        self.add(provider, signer, key, file, options).await
+    }
+
+    /// Broadcasts the transaction for an object that has been staged with the Object API.
+    async fn broadcast_add<C>(
+        &self,
+        provider: &impl Provider<C>,
+        signer: &mut impl Signer,
+        params: AddParams,
+        broadcast_mode: BroadcastMode,
+        gas_params: GasParams,
+    ) -> anyhow::Result<TxReceipt<Cid>>
+    where
+        C: Client + Send + Sync,
+    {
         let object = Some(MessageObject::new(
             params.key.clone(),
-            object_cid.0,
+            params.cid,
             self.address,
         ));
+        let serialized_params = RawBytes::serialize(params)?;
         let message = signer
             .transaction(
                 self.address,
@@ -248,21 +644,10 @@ impl ObjectStore {
                 AddObject as u64,
                 serialized_params,
                 object,
-                options.gas_params,
+                gas_params,
             )
             .await?;
-        let tx = provider
-            .perform(message, options.broadcast_mode, decode_cid)
-            .await?;
-        msg_bar.println(format!(
-            "{} Added object in {} (cid={}; size={})",
-            SPARKLE,
-            HumanDuration(started.elapsed()),
-            object_cid,
-            object_size
-        ));
-        msg_bar.finish_and_clear();
-        Ok(tx)
+        provider.perform(message, broadcast_mode, decode_cid).await
     }
 
     /// Uploads an object to the Object API for staging.
@@ -283,39 +668,34 @@ impl ObjectStore {
         S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
         Bytes: From<S::Ok>,
     {
-        let from = signer.address();
-        let params = AddParams {
-            key: key.into(),
-            cid: cid.0,
-            overwrite,
-            metadata,
+        let upload = self.presign(
+            &*signer,
+            key,
+            cid,
             size,
-        };
-        let serialized_params = RawBytes::serialize(params)?;
-
-        let message =
-            object_upload_message(from, self.address, AddObject as u64, serialized_params);
-        let singed_message = signer.sign_message(
-            message,
-            Some(MessageObject::new(key.into(), cid.0, self.address)),
+            PresignOptions {
+                overwrite,
+                metadata,
+            },
         )?;
-        let serialized_signed_message = fvm_ipld_encoding::to_vec(&singed_message)?;
-
-        let chain_id = match signer.subnet_id() {
-            Some(id) => id.chain_id(),
-            None => {
-                return Err(anyhow!("failed to get subnet ID from signer"));
-            }
-        };
+        self.upload_stream(provider, &upload, stream).await
+    }
 
+    /// Streams an object's content to the Object API with a signed upload message.
+    async fn upload_stream<S>(
+        &self,
+        provider: &impl ObjectProvider,
+        upload: &PresignedUpload,
+        stream: S,
+    ) -> anyhow::Result<Cid>
+    where
+        S: futures_core::stream::TryStream + Send + 'static,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+        Bytes: From<S::Ok>,
+    {
         let body = reqwest::Body::wrap_stream(stream);
         let response = provider
-            .upload(
-                body,
-                size,
-                general_purpose::URL_SAFE.encode(&serialized_signed_message),
-                chain_id.into(),
-            )
+            .upload(body, upload.size, upload.message.clone(), upload.chain_id)
             .await?;
 
         Ok(response)
@@ -332,6 +712,9 @@ impl ObjectStore {
     where
         C: Client + Send + Sync,
     {
+        if let Some(expected) = options.if_match {
+            self.check_match(provider, key, expected).await?;
+        }
         let params = DeleteParams { key: key.into() };
         let params = RawBytes::serialize(params)?;
         let message = signer
@@ -349,72 +732,513 @@ impl ObjectStore {
             .await
     }
 
+    /// Delete every object under a prefix.
+    ///
+    /// All keys are listed at a single height before anything is deleted. Deletes are then
+    /// signed in order with the signer's locally incremented sequence, and failures are
+    /// collected per key instead of stopping the whole operation. After a failure, the
+    /// signer's sequence is re-synced with the chain so that later deletes aren't signed
+    /// with a sequence the failed transaction never consumed.
+    pub async fn delete_prefix<C>(
+        &self,
+        provider: &impl Provider<C>,
+        signer: &mut impl Signer,
+        prefix: &str,
+        options: DeletePrefixOptions,
+    ) -> anyhow::Result<DeletePrefixSummary>
+    where
+        C: Client + Send + Sync,
+    {
+        let started = Instant::now();
+        let progress = &options.progress;
+
+        progress.step(1, 2);
+        progress.status(format!("Listing objects under '{}'...", prefix));
+        let keys: Vec<String> = self
+            .list_all(provider, prefix, options.height)
+            .await?
+            .0
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+
+        let mut summary = DeletePrefixSummary::default();
+        if options.dry_run {
+            summary.deleted = keys;
+            return Ok(summary);
+        }
+
+        progress.step(2, 2);
+        let total = keys.len();
+        for (i, key) in keys.into_iter().enumerate() {
+            progress.status(format!("Deleting {}/{} ({})", i + 1, total, key));
+            let result = self
+                .delete(
+                    provider,
+                    signer,
+                    &key,
+                    DeleteOptions {
+                        broadcast_mode: options.broadcast_mode,
+                        gas_params: options.gas_params.clone(),
+                        ..Default::default()
+                    },
+                )
+                .await;
+            match result {
+                Ok(_) if matches!(options.broadcast_mode, BroadcastMode::Async) => {
+                    summary.pending.push(key)
+                }
+                Ok(_) => summary.deleted.push(key),
+                Err(e) => {
+                    progress.warning(format!("Failed to delete {}: {}", key, e));
+                    summary.failed.push(DeleteFailure {
+                        key,
+                        error: e.to_string(),
+                    });
+                    signer
+                        .sync_sequence(provider)
+                        .await
+                        .context("failed to re-sync sequence after a failed delete")?;
+                }
+            }
+        }
+
+        progress.finished(format!(
+            "Deleted {} objects in {} (pending={}; failed={})",
+            summary.deleted.len(),
+            HumanDuration(started.elapsed()),
+            summary.pending.len(),
+            summary.failed.len()
+        ));
+        Ok(summary)
+    }
+
     /// Get an object at the given key, range, and height.
+    ///
+    /// Unless disabled with [`GetOptions::verify`], full-object gets are verified against the
+    /// object's CID. Bytes are written as they're streamed, so a writer may have received
+    /// data by the time a verification error is returned.
+    /// The writer is flushed before the object is verified, or shut down if the object is
+    /// decompressed.
     pub async fn get<W>(
         &self,
         provider: &(impl QueryProvider + ObjectProvider),
         key: &str,
-        mut writer: W,
+        writer: W,
         options: GetOptions,
     ) -> anyhow::Result<()>
     where
         W: AsyncWrite + Unpin + Send + 'static,
     {
         let started = Instant::now();
-        let bars = new_multi_bar(!options.show_progress);
-        let msg_bar = bars.add(new_message_bar());
+        let progress = &options.progress;
 
-        msg_bar.set_prefix("[1/2]");
-        msg_bar.set_message("Getting object info...");
-        let params = GetParams { key: key.into() };
-        let params = RawBytes::serialize(params)?;
-        let message = local_message(self.address, GetObject as u64, params);
-        let response = provider.call(message, options.height, decode_get).await?;
+        progress.step(1, 2);
+        progress.status("Getting object info...");
+        let response = self.get_object(provider, key, options.height).await?;
 
         let object = response
             .value
             .ok_or_else(|| anyhow!("object not found for key '{}'", key))?;
 
+        let mut decryptor = if !options.raw && is_encrypted(&object.metadata) {
+            let encryption_key = options
+                .encryption_key
+                .as_ref()
+                .ok_or_else(|| anyhow!("object is encrypted; an encryption key is required"))?;
+            if options.range.is_some() {
+                return Err(anyhow!("cannot get a range of an encrypted object"));
+            }
+            Some(Decryptor::new(encryption_key, &object.metadata)?)
+        } else {
+            None
+        };
+        let compression = if options.raw {
+            None
+        } else {
+            Compression::for_download(&object.metadata, progress)
+        };
+        if compression.is_some() && options.range.is_some() {
+            return Err(anyhow!(
+                "cannot get a range of a compressed object; request raw bytes instead"
+            ));
+        }
+        let mut writer: Pin<Box<dyn AsyncWrite + Send>> = match compression {
+            Some(compression) => compression.decoder(writer),
+            None => Box::pin(writer),
+        };
+
         let cid = cid::Cid::try_from(object.cid.0)?;
         if !object.resolved {
             return Err(anyhow!("object is not resolved"));
         }
-        msg_bar.set_prefix("[2/2]");
-        msg_bar.set_message(format!("Downloading {}... ", cid));
+        progress.step(2, 2);
 
-        let object_size = provider
-            .size(self.address, key, options.height.into())
-            .await?;
-        let pro_bar = bars.add(new_progress_bar(object_size));
-        let response = provider
-            .download(self.address, key, options.range, options.height.into())
+        // Pin the download to the height the object was queried at
+        let height = response.height.value();
+        let object_size = provider.size(self.address, key, height).await?;
+        let verify = options.verify && options.range.is_none();
+        if options.verify && !verify {
+            progress.warning(format!("Skipping verification of ranged get (cid={})", cid));
+        }
+        let mut builder = verify.then(CidBuilder::new);
+
+        progress.report(ProgressEvent::DownloadStarted {
+            cid: Cid::from(cid),
+            size: object_size,
+            offset: 0,
+        });
+        let mut stream = if options.parallel > 1 && options.range.is_none() {
+            // Fetch parts concurrently and yield them in order
+            let address = self.address;
+            let parts = (0..object_size)
+                .step_by(DOWNLOAD_PART_SIZE)
+                .map(|start| (start, min(start + DOWNLOAD_PART_SIZE, object_size) - 1));
+            stream::iter(parts)
+                .map(|(start, end)| async move {
+                    let range = Some(format!("{}-{}", start, end));
+                    let response = provider.download(address, key, range, height).await?;
+                    let part = response.bytes().await?;
+                    if part.len() != end - start + 1 {
+                        return Err(anyhow!(
+                            "unexpected part size for range {}-{}: {} bytes",
+                            start,
+                            end,
+                            part.len()
+                        ));
+                    }
+                    Ok(part)
+                })
+                .buffered(options.parallel)
+                .boxed()
+        } else {
+            let response = provider
+                .download(self.address, key, options.range, height)
+                .await?;
+            response
+                .bytes_stream()
+                .map(|item| item.map_err(anyhow::Error::from))
+                .boxed()
+        };
+        let mut downloaded = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            match decryptor.as_mut() {
+                Some(decryptor) => writer.write_all(&decryptor.push(&chunk)?).await?,
+                None => writer.write_all(&chunk).await?,
+            }
+            if let Some(builder) = builder.as_mut() {
+                builder.push(&chunk)?;
+            }
+            downloaded = min(downloaded + chunk.len(), object_size);
+            progress.report(ProgressEvent::Downloaded { bytes: downloaded });
+        }
+        if let Some(decryptor) = decryptor {
+            writer.write_all(&decryptor.finish()?).await?;
+        }
+        if compression.is_some() {
+            // Finish decoding, which also shuts down the writer
+            writer.shutdown().await?;
+        } else {
+            writer.flush().await?;
+        }
+        progress.report(ProgressEvent::DownloadFinished);
+
+        // Verify downloaded bytes against the object's CID
+        if let Some(builder) = builder {
+            let downloaded_cid = builder.finish()?;
+            if downloaded_cid.0 != cid {
+                return Err(anyhow!(
+                    "cannot verify object; downloaded cid {} does not match {}",
+                    downloaded_cid,
+                    cid
+                ));
+            }
+        }
+        progress.finished(format!(
+            "Downloaded detached object in {} (cid={})",
+            HumanDuration(started.elapsed()),
+            cid
+        ));
+        Ok(())
+    }
+
+    /// Download an object to a local file, resuming a previous partial download if one exists.
+    ///
+    /// The query height of the first attempt is pinned in a journal next to the file
+    /// (`<PATH>.adm-download`), and the completed file is verified against the object's CID.
+    pub async fn download_to_path(
+        &self,
+        provider: &(impl QueryProvider + ObjectProvider),
+        key: &str,
+        path: impl AsRef<Path>,
+        options: DownloadOptions,
+    ) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        let journal_path = {
+            let mut p = path.to_path_buf().into_os_string();
+            p.push(".adm-download");
+            PathBuf::from(p)
+        };
+        let started = Instant::now();
+        let progress = &options.progress;
+
+        // Pin the height of the first attempt
+        progress.step(1, 3);
+        progress.status("Getting object info...");
+        let journal = DownloadJournal::load(&journal_path)
+            .await?
+            .filter(|j| j.matches(self.address, key));
+        let height = match (&journal, options.height) {
+            (Some(journal), FvmQueryHeight::Height(height)) if height != journal.height => {
+                return Err(anyhow!(
+                    "an interrupted download of '{}' is pinned to height {}, not {}; remove {} to start over",
+                    key,
+                    journal.height,
+                    height,
+                    journal_path.display()
+                ));
+            }
+            (Some(journal), _) => FvmQueryHeight::Height(journal.height),
+            (None, height) => height,
+        };
+        let response = self.get_object(provider, key, height).await?;
+        let object = response
+            .value
+            .ok_or_else(|| anyhow!("object not found for key '{}'", key))?;
+        if !object.resolved {
+            return Err(anyhow!("object is not resolved"));
+        }
+        let encoded = is_encrypted(&object.metadata)
+            || Compression::for_download(&object.metadata, progress).is_some();
+        if encoded {
+            return Err(anyhow!(
+                "cannot download an encrypted or compressed object to a path; use get instead"
+            ));
+        }
+        let cid = Cid::from(cid::Cid::try_from(object.cid.0)?);
+        let height = response.height.value();
+
+        // Start over unless the journal describes the same object version
+        let resume = journal.is_some_and(|j| j.cid == cid && j.height == height);
+        if !resume {
+            DownloadJournal::new(self.address, key, cid, height)
+                .save(&journal_path)
+                .await?;
+        }
+        let mut file = tokio::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .read(true)
+            .truncate(!resume)
+            .open(path)
             .await?;
-        let mut stream = response.bytes_stream();
-        let mut progress = 0;
-        while let Some(item) = stream.next().await {
-            match item {
-                Ok(chunk) => {
-                    writer.write_all(&chunk).await?;
-                    progress = min(progress + chunk.len(), object_size);
-                    pro_bar.set_position(progress as u64);
-                }
-                Err(e) => {
-                    return Err(anyhow!(e));
-                }
+        let object_size = provider.size(self.address, key, height).await?;
+        let mut offset = file.metadata().await?.len() as usize;
+        if offset > object_size {
+            file.set_len(0).await?;
+            offset = 0;
+        }
+
+        progress.step(2, 3);
+        if offset < object_size {
+            progress.report(ProgressEvent::DownloadStarted {
+                cid,
+                size: object_size,
+                offset,
+            });
+            file.seek(SeekFrom::Start(offset as u64)).await?;
+            let range = Some(format!("{}-{}", offset, object_size - 1));
+            let response = provider.download(self.address, key, range, height).await?;
+            let mut stream = response.bytes_stream();
+            let mut downloaded = offset;
+            while let Some(chunk) = stream.next().await {
+                let chunk = chunk?;
+                file.write_all(&chunk).await?;
+                downloaded = min(downloaded + chunk.len(), object_size);
+                progress.report(ProgressEvent::Downloaded { bytes: downloaded });
             }
+            file.flush().await?;
+            progress.report(ProgressEvent::DownloadFinished);
         }
-        pro_bar.finish_and_clear();
-        msg_bar.println(format!(
-            "{} Downloaded detached object in {} (cid={})",
-            SPARKLE,
+
+        // Verify the completed file against the object's CID
+        progress.step(3, 3);
+        progress.status(format!("Verifying {}...", cid));
+        file.rewind().await?;
+        let (file_cid, _) = generate_cid(&mut file, progress).await?;
+        DownloadJournal::remove(&journal_path).await?;
+        if file_cid != cid {
+            return Err(anyhow!(
+                "cannot verify object; downloaded cid {} does not match {}",
+                file_cid,
+                cid
+            ));
+        }
+        progress.finished(format!(
+            "Downloaded detached object in {} (cid={})",
             HumanDuration(started.elapsed()),
             cid
         ));
+        Ok(())
+    }
+
+    /// Get an object's info and content length without downloading it.
+    ///
+    /// Returns `None` if no object exists for the key at the given height.
+    /// The content length is only available once the object has been resolved.
+    pub async fn head(
+        &self,
+        provider: &(impl QueryProvider + ObjectProvider),
+        key: &str,
+        height: FvmQueryHeight,
+    ) -> anyhow::Result<Option<ObjectHead>> {
+        let response = self.get_object(provider, key, height).await?;
+        let object = match response.value {
+            Some(object) => object,
+            None => return Ok(None),
+        };
+        let height = response.height.value();
+        let content_length = if object.resolved {
+            Some(provider.size(self.address, key, height).await?)
+        } else {
+            None
+        };
+        Ok(Some(ObjectHead {
+            key: key.into(),
+            object,
+            content_length,
+            height,
+        }))
+    }
+
+    /// Compare local content with the object stored under a key at the given height.
+    ///
+    /// If the object was added with compression, the local content is compressed the same way
+    /// before its CID is computed. Encrypted objects cannot be verified against local content,
+    /// since their ciphertext is not reproducible.
+    pub async fn verify<R>(
+        &self,
+        provider: &impl QueryProvider,
+        key: &str,
+        mut reader: R,
+        height: FvmQueryHeight,
+    ) -> anyhow::Result<Verification>
+    where
+        R: AsyncRead + Unpin + Send,
+    {
+        let response = self.get_object(provider, key, height).await?;
+        let object = response
+            .value
+            .ok_or_else(|| anyhow!("object not found for key '{}'", key))?;
+        if is_encrypted(&object.metadata) {
+            return Err(anyhow!(
+                "cannot verify an encrypted object against local content"
+            ));
+        }
+        let remote_cid = Cid::from(cid::Cid::try_from(object.cid.0)?);
+        let (local_cid, _) = match Compression::from_metadata(&object.metadata)? {
+            Some(compression) => compute_cid(&mut compression.encoder(&mut reader)).await?,
+            None => compute_cid(&mut reader).await?,
+        };
+        Ok(Verification {
+            key: key.into(),
+            local_cid,
+            remote_cid,
+            matches: local_cid == remote_cid,
+            height: response.height.value(),
+        })
+    }
+
+    /// Wait for an object to be resolved by validators.
+    ///
+    /// The object is polled at the latest committed height with backoff until it is
+    /// resolved, or an error is returned once the timeout has elapsed.
+    pub async fn wait_resolved(
+        &self,
+        provider: &impl QueryProvider,
+        key: &str,
+        timeout: Duration,
+    ) -> anyhow::Result<Object> {
+        self.poll_resolved(provider, key, None, timeout).await
+    }
+
+    /// Poll an object until it is resolved, optionally requiring a specific CID.
+    /// A missing object or a different CID is treated as not yet committed.
+    async fn poll_resolved(
+        &self,
+        provider: &impl QueryProvider,
+        key: &str,
+        cid: Option<Cid>,
+        timeout: Duration,
+    ) -> anyhow::Result<Object> {
+        let deadline = Instant::now() + timeout;
+        let mut delay = RESOLVE_POLL_MIN;
+        loop {
+            let response = self
+                .get_object(provider, key, FvmQueryHeight::Committed)
+                .await?;
+            if let Some(object) = response.value {
+                let matches = match cid {
+                    Some(cid) => Cid::from(cid::Cid::try_from(object.cid.0.clone())?) == cid,
+                    None => true,
+                };
+                if matches && object.resolved {
+                    return Ok(object);
+                }
+            }
 
-        msg_bar.finish_and_clear();
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(anyhow!(
+                    "timed out waiting for object '{}' to resolve after {}",
+                    key,
+                    HumanDuration(timeout)
+                ));
+            }
+            sleep(min(delay, deadline - now)).await;
+            delay = min(delay * 2, RESOLVE_POLL_MAX);
+        }
+    }
+
+    /// Check that a key holds an object with the expected CID in pending state.
+    async fn check_match(
+        &self,
+        provider: &impl QueryProvider,
+        key: &str,
+        expected: Cid,
+    ) -> anyhow::Result<()> {
+        let response = self
+            .get_object(provider, key, FvmQueryHeight::Pending)
+            .await?;
+        let actual = match response.value {
+            Some(object) => Some(Cid::from(cid::Cid::try_from(object.cid.0)?)),
+            None => None,
+        };
+        if actual != Some(expected) {
+            return Err(ConflictError {
+                key: key.into(),
+                expected,
+                actual,
+            }
+            .into());
+        }
         Ok(())
     }
 
+    /// Get an object by key, returning the height at which it was queried.
+    async fn get_object(
+        &self,
+        provider: &impl QueryProvider,
+        key: &str,
+        height: FvmQueryHeight,
+    ) -> anyhow::Result<QueryResponse<Option<Object>>> {
+        let params = GetParams { key: key.into() };
+        let params = RawBytes::serialize(params)?;
+        let message = local_message(self.address, GetObject as u64, params);
+        provider.call(message, height, decode_get).await
+    }
+
     /// Query for objects with params at the given height.
     ///
     /// Use [`QueryOptions`] for filtering and pagination.
@@ -423,6 +1247,16 @@ impl ObjectStore {
         provider: &impl QueryProvider,
         options: QueryOptions,
     ) -> anyhow::Result<ObjectList> {
+        let response = self.query_page(provider, options).await?;
+        Ok(response.value)
+    }
+
+    /// Query for a single page of objects, returning the height at which it was queried.
+    async fn query_page(
+        &self,
+        provider: &impl QueryProvider,
+        options: QueryOptions,
+    ) -> anyhow::Result<QueryResponse<ObjectList>> {
         let params = fendermint_actor_objectstore::ListParams {
             prefix: options.prefix.into(),
             delimiter: options.delimiter.into(),
@@ -431,46 +1265,165 @@ impl ObjectStore {
         };
         let params = RawBytes::serialize(params)?;
         let message = local_message(self.address, ListObjects as u64, params);
-        let response = provider.call(message, options.height, decode_list).await?;
-        Ok(response.value)
+        provider.call(message, options.height, decode_list).await
     }
-}
 
-async fn generate_cid<R: AsyncRead + Unpin>(
-    reader: &mut R,
-    mut buffer: Vec<u8>,
-    reader_size: &mut usize,
-    mut adder: FileAdder,
-    mut chunk: Cid,
-    msg_bar: &indicatif::ProgressBar,
-    object_size: &mut usize,
-) -> Result<Cid, anyhow::Error> {
-    loop {
-        match reader.read(&mut buffer).await {
-            Ok(0) => {
-                break;
+    /// Query for all objects and common prefixes matching the params.
+    ///
+    /// Pages are fetched transparently as the stream is polled, starting from
+    /// [`QueryOptions::offset`] and using [`QueryOptions::limit`] as the page size.
+    /// All pages are queried at the height of the first page, which is returned with each entry.
+    pub fn query_stream<'a>(
+        &'a self,
+        provider: &'a impl QueryProvider,
+        options: QueryOptions,
+    ) -> impl Stream<Item = anyhow::Result<QueryResponse<QueryItem>>> + 'a {
+        async_stream::try_stream! {
+            // Common prefixes are collected from every key the actor visits, so the same
+            // prefixes can be returned on every page
+            let mut seen_prefixes = HashSet::new();
+            let mut pages = pin!(self.query_pages(provider, options));
+            while let Some(page) = pages.next().await {
+                let QueryResponse { height, value: list } = page?;
+                for (key, object) in list.objects {
+                    let value = QueryItem::Object(String::from_utf8(key)?, object);
+                    yield QueryResponse { height, value };
+                }
+                for common_prefix in list.common_prefixes {
+                    let common_prefix = String::from_utf8(common_prefix)?;
+                    if seen_prefixes.insert(common_prefix.clone()) {
+                        let value = QueryItem::CommonPrefix(common_prefix);
+                        yield QueryResponse { height, value };
+                    }
+                }
             }
-            Ok(n) => {
-                *reader_size += n;
-                let (leaf, n) = adder.push(&buffer[..n]);
-                for (c, _) in leaf {
-                    chunk = Cid::from(cid::Cid::try_from(c.to_bytes())?);
-                    msg_bar.set_message(format!("Processed chunk: {}", c));
+        }
+    }
+
+    /// Query for consecutive pages until a page without objects is returned.
+    /// All pages are queried at the height of the first page.
+    ///
+    /// The offset only skips objects, so it's advanced by the number of objects in each page.
+    fn query_pages<'a>(
+        &'a self,
+        provider: &'a impl QueryProvider,
+        mut options: QueryOptions,
+    ) -> impl Stream<Item = anyhow::Result<QueryResponse<ObjectList>>> + 'a {
+        async_stream::try_stream! {
+            loop {
+                let response = self.query_page(provider, options.clone()).await?;
+                options.height = FvmQueryHeight::Height(response.height.value());
+                let count = response.value.objects.len();
+                yield response;
+                if count == 0 {
+                    break;
                 }
-                *object_size += n;
+                options.offset += count as u64;
             }
-            Err(e) => {
-                return Err(e.into());
+        }
+    }
+
+    /// List every object under a prefix, ignoring the delimiter.
+    /// All pages are queried at the height of the first page, which is returned with the objects.
+    async fn list_all(
+        &self,
+        provider: &impl QueryProvider,
+        prefix: &str,
+        height: FvmQueryHeight,
+    ) -> anyhow::Result<(Vec<(String, Object)>, u64)> {
+        let mut height = height;
+        let options = QueryOptions {
+            prefix: prefix.into(),
+            delimiter: "".into(),
+            height,
+            ..Default::default()
+        };
+        let mut objects = Vec::new();
+        let mut items = pin!(self.query_stream(provider, options));
+        while let Some(item) = items.next().await {
+            let item = item?;
+            height = FvmQueryHeight::Height(item.height.value());
+            if let QueryItem::Object(key, object) = item.value {
+                objects.push((key, object));
             }
         }
+        Ok((objects, height.into()))
     }
-    let unixfs_iterator = adder.finish();
-    let last = unixfs_iterator.last();
-    let object_cid = match last {
-        Some((c, _)) => Cid::from(cid::Cid::try_from(c.to_bytes())?),
-        None => chunk,
+}
+
+/// Parse a single HTTP byte range, e.g., `bytes=0-99`, into inclusive offsets within an object
+/// of the given size.
+///
+/// Suffix ranges and open ranges are supported, and ends past the object are clamped.
+/// Returns `None` if the range is malformed or not satisfiable.
+pub fn parse_range(value: &str, size: usize) -> Option<(usize, usize)> {
+    let (start, end) = value.strip_prefix("bytes=")?.split_once('-')?;
+    if size == 0 {
+        return None;
+    }
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", suffix) => {
+            let suffix: usize = suffix.parse().ok()?;
+            (size.checked_sub(suffix.min(size))?, size - 1)
+        }
+        (start, "") => (start.parse().ok()?, size - 1),
+        (start, end) => (
+            start.parse().ok()?,
+            end.parse::<usize>().ok()?.min(size - 1),
+        ),
     };
-    Ok(object_cid)
+    (start <= end && start < size).then_some((start, end))
+}
+
+/// Compute the CID of an object's content exactly as [`ObjectStore::add`] does,
+/// without uploading it. Returns the CID along with the object size.
+///
+/// The CID is computed over the bytes as given, so content that is added with compression
+/// must be compressed first.
+pub async fn compute_cid<R: AsyncRead + Unpin>(reader: &mut R) -> anyhow::Result<(Cid, usize)> {
+    generate_cid(reader, &Progress::default()).await
+}
+
+async fn generate_cid<R: AsyncRead + Unpin>(
+    reader: &mut R,
+    progress: &Progress,
+) -> anyhow::Result<(Cid, usize)> {
+    let mut builder = CidBuilder::new();
+    let mut buffer = vec![0; CHUNK_SIZE];
+    loop {
+        let n = reader.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        for chunk in builder.push(&buffer[..n])? {
+            progress.report(ProgressEvent::Hashed { chunk });
+        }
+    }
+    let object_size = builder.size();
+    Ok((builder.finish()?, object_size))
+}
+
+/// Stream a reader for uploading, reporting the bytes uploaded so far.
+fn upload_progress_stream<R>(
+    reader: R,
+    size: usize,
+    progress: Progress,
+) -> impl Stream<Item = std::io::Result<Bytes>> + Send + 'static
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    let mut stream = ReaderStream::new(reader);
+    async_stream::stream! {
+        let mut uploaded: usize = 0;
+        while let Some(chunk) = stream.next().await {
+            if let Ok(chunk) = &chunk {
+                uploaded = min(uploaded + chunk.len(), size);
+                progress.report(ProgressEvent::Uploaded { bytes: uploaded });
+            }
+            yield chunk;
+        }
+        progress.report(ProgressEvent::UploadFinished);
+    }
 }
 
 fn decode_get(deliver_tx: &DeliverTx) -> anyhow::Result<Option<Object>> {
@@ -483,3 +1436,139 @@ fn decode_list(deliver_tx: &DeliverTx) -> anyhow::Result<ObjectList> {
     let data = decode_bytes(deliver_tx)?;
     fvm_ipld_encoding::from_slice(&data).map_err(|e| anyhow!("error parsing as ObjectList: {e}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use futures::TryStreamExt;
+
+    use super::mock::{cid_of, object, MockQueryProvider};
+    use super::*;
+
+    fn keys(items: Vec<QueryResponse<QueryItem>>) -> Vec<String> {
+        items
+            .into_iter()
+            .map(|item| match item.value {
+                QueryItem::Object(key, _) => key,
+                QueryItem::CommonPrefix(prefix) => prefix,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), Some((0, 99)));
+        assert_eq!(parse_range("bytes=900-", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=-100", 1000), Some((900, 999)));
+        assert_eq!(parse_range("bytes=-2000", 1000), Some((0, 999)));
+        assert_eq!(parse_range("bytes=500-2000", 1000), Some((500, 999)));
+        assert_eq!(parse_range("bytes= 10 - 19", 1000), Some((10, 19)));
+        assert_eq!(parse_range("bytes=1000-", 1000), None);
+        assert_eq!(parse_range("bytes=10-5", 1000), None);
+        assert_eq!(parse_range("bytes=-0", 1000), None);
+        assert_eq!(parse_range("bytes=0-9,20-29", 1000), None);
+        assert_eq!(parse_range("items=0-1", 1000), None);
+        assert_eq!(parse_range("bytes=0-0", 0), None);
+    }
+
+    #[tokio::test]
+    async fn test_check_match() {
+        let mut provider = MockQueryProvider::new(10);
+        provider.set(1, &[("foo", object(b"foo", &[]))]);
+        let store = ObjectStore::attach(Address::new_id(1001));
+
+        store
+            .check_match(&provider, "foo", cid_of(b"foo"))
+            .await
+            .unwrap();
+
+        let err = store
+            .check_match(&provider, "foo", cid_of(b"bar"))
+            .await
+            .unwrap_err();
+        let conflict = err.downcast_ref::<ConflictError>().unwrap();
+        assert_eq!(conflict.key, "foo");
+        assert_eq!(conflict.expected, cid_of(b"bar"));
+        assert_eq!(conflict.actual, Some(cid_of(b"foo")));
+
+        let err = store
+            .check_match(&provider, "bar", cid_of(b"bar"))
+            .await
+            .unwrap_err();
+        let conflict = err.downcast_ref::<ConflictError>().unwrap();
+        assert_eq!(conflict.actual, None);
+    }
+
+    #[tokio::test]
+    async fn test_query_stream() {
+        let mut provider = MockQueryProvider::new(10);
+        let objects: Vec<_> = ["a", "b", "c/1", "c/2", "d", "e/1", "f"]
+            .into_iter()
+            .map(|key| (key, object(key.as_bytes(), &[])))
+            .collect();
+        provider.set(1, &objects);
+        // Later pages must not see state written after the first page
+        provider.set(11, &[]);
+        let store = ObjectStore::attach(Address::new_id(1001));
+
+        let options = QueryOptions {
+            limit: 2,
+            ..Default::default()
+        };
+        let items: Vec<_> = store
+            .query_stream(&provider, options)
+            .try_collect()
+            .await
+            .unwrap();
+        assert!(items.iter().all(|item| item.height.value() == 10));
+        assert_eq!(keys(items), ["a", "b", "c/", "d", "f", "e/"]);
+        // The third page only repeats common prefixes and ends the stream
+        assert_eq!(*provider.queries.lock().unwrap(), [10, 10, 10]);
+    }
+
+    #[tokio::test]
+    async fn test_query_stream_common_prefixes_only() {
+        let mut provider = MockQueryProvider::new(10);
+        provider.set(
+            1,
+            &[("c/1", object(b"c/1", &[])), ("e/1", object(b"e/1", &[]))],
+        );
+        let store = ObjectStore::attach(Address::new_id(1001));
+
+        let options = QueryOptions {
+            limit: 1,
+            ..Default::default()
+        };
+        let items: Vec<_> = store
+            .query_stream(&provider, options)
+            .try_collect()
+            .await
+            .unwrap();
+        assert_eq!(keys(items), ["c/", "e/"]);
+        assert_eq!(*provider.queries.lock().unwrap(), [10]);
+    }
+
+    #[tokio::test]
+    async fn test_list_all() {
+        let mut provider = MockQueryProvider::new(10);
+        provider.set(
+            1,
+            &[
+                ("a", object(b"a", &[])),
+                ("c/1", object(b"c/1", &[])),
+                ("c/2/3", object(b"c/2/3", &[])),
+                ("d/1", object(b"d/1", &[])),
+            ],
+        );
+        provider.set(11, &[]);
+        let store = ObjectStore::attach(Address::new_id(1001));
+
+        let (objects, height) = store
+            .list_all(&provider, "c/", FvmQueryHeight::Committed)
+            .await
+            .unwrap();
+        let keys: Vec<_> = objects.into_iter().map(|(key, _)| key).collect();
+        assert_eq!(keys, ["c/1", "c/2/3"]);
+        assert_eq!(height, 10);
+        assert_eq!(*provider.queries.lock().unwrap(), [10, 10]);
+    }
+}
@@ -0,0 +1,349 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::anyhow;
+use fendermint_actor_objectstore::Object;
+use fendermint_vm_message::query::FvmQueryHeight;
+use serde::Serialize;
+
+use adm_provider::{query::QueryProvider, response::Cid};
+
+use super::ObjectStore;
+
+/// A version of an object, as returned by [`ObjectStore::history`].
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ObjectVersion {
+    /// First block height at which this version was visible.
+    pub height: u64,
+    /// Object CID, or `None` if the key was deleted at this height.
+    pub cid: Option<Cid>,
+    /// Object size in bytes.
+    pub size: Option<usize>,
+    /// Object metadata.
+    pub metadata: HashMap<String, String>,
+}
+
+/// The parts of an object that identify a version.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ObjectState {
+    /// Object CID.
+    pub cid: Cid,
+    /// Object size in bytes.
+    pub size: usize,
+    /// Object metadata.
+    pub metadata: HashMap<String, String>,
+}
+
+/// An object under a key, as reported by [`ObjectStore::diff`].
+#[derive(Clone, Debug, Serialize)]
+pub struct DiffEntry {
+    /// Object key.
+    pub key: String,
+    /// Object state.
+    #[serde(flatten)]
+    pub object: ObjectState,
+}
+
+/// A key whose object changed, as reported by [`ObjectStore::diff`].
+#[derive(Clone, Debug, Serialize)]
+pub struct ModifiedEntry {
+    /// Object key.
+    pub key: String,
+    /// Object state at the first height.
+    pub from: ObjectState,
+    /// Object state at the second height.
+    pub to: ObjectState,
+}
+
+/// Changes to an object store between two block heights.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct StoreDiff {
+    /// First block height.
+    pub from_height: u64,
+    /// Second block height.
+    pub to_height: u64,
+    /// Keys that exist at the second height but not the first.
+    pub added: Vec<DiffEntry>,
+    /// Keys that exist at the first height but not the second.
+    pub removed: Vec<DiffEntry>,
+    /// Keys whose CID, size, or metadata differ between the heights.
+    pub modified: Vec<ModifiedEntry>,
+}
+
+impl ObjectState {
+    fn from_object(object: Object) -> anyhow::Result<Self> {
+        Ok(Self {
+            cid: Cid::from(cid::Cid::try_from(object.cid.0)?),
+            size: object.size,
+            metadata: object.metadata,
+        })
+    }
+}
+
+impl ObjectStore {
+    /// Find the versions of an object between two block heights.
+    ///
+    /// Heights where the key's CID, size, or metadata changed are found by bisecting
+    /// `GetObject` queries, so a change that is reverted within a range that otherwise
+    /// looks unchanged is not detected. The first version is the object as of `from_height`,
+    /// if it existed. The node must retain state for every queried height.
+    pub async fn history(
+        &self,
+        provider: &impl QueryProvider,
+        key: &str,
+        from_height: u64,
+        to_height: FvmQueryHeight,
+    ) -> anyhow::Result<Vec<ObjectVersion>> {
+        let response = self.get_object(provider, key, to_height).await?;
+        let to_height = response.height.value();
+        if from_height > to_height {
+            return Err(anyhow!(
+                "from height {} is after to height {}",
+                from_height,
+                to_height
+            ));
+        }
+        let to_state = response.value.map(ObjectState::from_object).transpose()?;
+        let from_state = self.version_at(provider, key, from_height).await?;
+
+        let mut versions = Vec::new();
+        if let Some(state) = &from_state {
+            versions.push(version(from_height, Some(state.clone())));
+        }
+
+        // Narrow down every range whose ends differ to the height of the change
+        let mut ranges = vec![(from_height, from_state, to_height, to_state)];
+        while let Some((low, low_state, high, high_state)) = ranges.pop() {
+            if low_state == high_state {
+                continue;
+            }
+            if high - low <= 1 {
+                versions.push(version(high, high_state));
+                continue;
+            }
+            let mid = low + (high - low) / 2;
+            let mid_state = self.version_at(provider, key, mid).await?;
+            ranges.push((low, low_state, mid, mid_state.clone()));
+            ranges.push((mid, mid_state, high, high_state));
+        }
+        versions.sort_by_key(|v| v.height);
+        Ok(versions)
+    }
+
+    /// Compare the objects under a prefix between two block heights.
+    ///
+    /// All objects under the prefix are listed at both heights, ignoring the delimiter, and
+    /// keys are reported as added, removed, or modified by comparing CID, size, and metadata.
+    pub async fn diff(
+        &self,
+        provider: &impl QueryProvider,
+        from_height: u64,
+        to_height: u64,
+        prefix: &str,
+    ) -> anyhow::Result<StoreDiff> {
+        let mut from = self.snapshot(provider, prefix, from_height).await?;
+        let to = self.snapshot(provider, prefix, to_height).await?;
+
+        let mut diff = StoreDiff {
+            from_height,
+            to_height,
+            ..Default::default()
+        };
+        for (key, to_object) in to {
+            match from.remove(&key) {
+                None => diff.added.push(DiffEntry {
+                    key,
+                    object: to_object,
+                }),
+                Some(from_object) if from_object != to_object => {
+                    diff.modified.push(ModifiedEntry {
+                        key,
+                        from: from_object,
+                        to: to_object,
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        diff.removed = from
+            .into_iter()
+            .map(|(key, object)| DiffEntry { key, object })
+            .collect();
+        Ok(diff)
+    }
+
+    /// List the state of every object under a prefix at a block height, ordered by key.
+    async fn snapshot(
+        &self,
+        provider: &impl QueryProvider,
+        prefix: &str,
+        height: u64,
+    ) -> anyhow::Result<BTreeMap<String, ObjectState>> {
+        self.list_all(provider, prefix, FvmQueryHeight::Height(height))
+            .await?
+            .0
+            .into_iter()
+            .map(|(key, object)| Ok((key, ObjectState::from_object(object)?)))
+            .collect()
+    }
+
+    /// Get the state of an object at a block height.
+    async fn version_at(
+        &self,
+        provider: &impl QueryProvider,
+        key: &str,
+        height: u64,
+    ) -> anyhow::Result<Option<ObjectState>> {
+        let response = self
+            .get_object(provider, key, FvmQueryHeight::Height(height))
+            .await?;
+        response.value.map(ObjectState::from_object).transpose()
+    }
+}
+
+fn version(height: u64, state: Option<ObjectState>) -> ObjectVersion {
+    match state {
+        Some(state) => ObjectVersion {
+            height,
+            cid: Some(state.cid),
+            size: Some(state.size),
+            metadata: state.metadata,
+        },
+        None => ObjectVersion {
+            height,
+            cid: None,
+            size: None,
+            metadata: HashMap::new(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+
+    use super::super::mock::{cid_of, object, MockQueryProvider};
+    use super::*;
+    use crate::machine::Machine;
+
+    /// "foo" is created at 40, modified at 70, has its metadata changed at 80, and is deleted at 90.
+    fn provider() -> MockQueryProvider {
+        let mut provider = MockQueryProvider::new(100);
+        provider.set(40, &[("foo", object(b"v1", &[]))]);
+        provider.set(70, &[("foo", object(b"v2", &[]))]);
+        provider.set(80, &[("foo", object(b"v2", &[("color", "red")]))]);
+        provider.set(90, &[]);
+        provider
+    }
+
+    async fn history(from_height: u64, to_height: FvmQueryHeight) -> Vec<(u64, Option<Cid>)> {
+        let store = ObjectStore::attach(Address::new_id(1001));
+        store
+            .history(&provider(), "foo", from_height, to_height)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|v| (v.height, v.cid))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_history() {
+        let v1 = Some(cid_of(b"v1"));
+        let v2 = Some(cid_of(b"v2"));
+        assert_eq!(
+            history(10, FvmQueryHeight::Height(100)).await,
+            [(40, v1), (70, v2), (80, v2), (90, None)]
+        );
+
+        // The first version is the object as of the from height
+        assert_eq!(
+            history(50, FvmQueryHeight::Committed).await,
+            [(50, v1), (70, v2), (80, v2), (90, None)]
+        );
+
+        // Changes at the ends of a range
+        assert_eq!(history(39, FvmQueryHeight::Height(40)).await, [(40, v1)]);
+        assert_eq!(history(40, FvmQueryHeight::Height(40)).await, [(40, v1)]);
+        assert_eq!(
+            history(89, FvmQueryHeight::Height(90)).await,
+            [(89, v2), (90, None)]
+        );
+        assert!(history(90, FvmQueryHeight::Height(100)).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_history_metadata() {
+        let store = ObjectStore::attach(Address::new_id(1001));
+        let versions = store
+            .history(&provider(), "foo", 75, FvmQueryHeight::Height(85))
+            .await
+            .unwrap();
+        assert_eq!(versions.len(), 2);
+        assert!(versions[0].metadata.is_empty());
+        assert_eq!(versions[1].height, 80);
+        assert_eq!(versions[1].metadata["color"], "red");
+    }
+
+    #[tokio::test]
+    async fn test_history_never_existed() {
+        let store = ObjectStore::attach(Address::new_id(1001));
+        let provider = provider();
+        let versions = store
+            .history(&provider, "bar", 10, FvmQueryHeight::Height(100))
+            .await
+            .unwrap();
+        assert!(versions.is_empty());
+        // Both ends are missing, so nothing is bisected
+        assert_eq!(provider.queries.lock().unwrap().len(), 2);
+
+        assert!(store
+            .history(&provider, "foo", 50, FvmQueryHeight::Height(40))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_diff() {
+        let mut provider = MockQueryProvider::new(100);
+        provider.set(
+            10,
+            &[
+                ("docs/a", object(b"a", &[])),
+                ("docs/b", object(b"b", &[])),
+                ("docs/c", object(b"c", &[])),
+                ("docs/d", object(b"d", &[])),
+                ("other", object(b"other", &[])),
+            ],
+        );
+        provider.set(
+            20,
+            &[
+                ("docs/a", object(b"a", &[])),
+                ("docs/b", object(b"b2", &[])),
+                ("docs/d", object(b"d", &[("color", "red")])),
+                ("docs/e", object(b"e", &[])),
+            ],
+        );
+        let store = ObjectStore::attach(Address::new_id(1001));
+
+        let diff = store.diff(&provider, 10, 20, "docs/").await.unwrap();
+        assert_eq!((diff.from_height, diff.to_height), (10, 20));
+        let keys = |entries: &[DiffEntry]| -> Vec<String> {
+            entries.iter().map(|e| e.key.clone()).collect()
+        };
+        assert_eq!(keys(&diff.added), ["docs/e"]);
+        assert_eq!(keys(&diff.removed), ["docs/c"]);
+        let modified: Vec<_> = diff.modified.iter().map(|e| e.key.as_str()).collect();
+        assert_eq!(modified, ["docs/b", "docs/d"]);
+        assert_eq!(diff.modified[0].from.cid, cid_of(b"b"));
+        assert_eq!(diff.modified[0].to.cid, cid_of(b"b2"));
+        assert_eq!(diff.modified[1].to.metadata["color"], "red");
+
+        // Nothing changes between heights with the same state
+        let diff = store.diff(&provider, 20, 30, "").await.unwrap();
+        assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.modified.is_empty());
+    }
+}
@@ -0,0 +1,587 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::{Cursor, SeekFrom};
+use std::path::Path;
+
+use anyhow::anyhow;
+use async_tempfile::TempFile;
+use cid::multihash::Multihash;
+use fendermint_vm_message::query::FvmQueryHeight;
+use futures::StreamExt;
+use fvm_ipld_encoding::DAG_CBOR;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tendermint_rpc::Client;
+use tokio::{
+    fs::File,
+    io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader},
+    time::Instant,
+};
+use unixfs_v1::file::visit::IdleFileVisit;
+
+use adm_provider::{
+    message::GasParams, object::ObjectProvider, query::QueryProvider, response::Cid,
+    tx::BroadcastMode, Provider,
+};
+use adm_signer::Signer;
+
+use crate::progress::{HumanDuration, Progress, ProgressEvent};
+
+use super::{AddOptions, CidBuilder, ObjectStore};
+
+/// Multihash code for SHA2-256.
+const SHA2_256: u64 = 0x12;
+/// Version of the manifest format written by [`ObjectStore::export`].
+const MANIFEST_VERSION: u64 = 1;
+
+/// Object store export options.
+#[derive(Clone, Debug, Default)]
+pub struct ExportOptions {
+    /// The prefix to filter objects by.
+    pub prefix: String,
+    /// Query block height.
+    /// All objects are listed and downloaded at the height of the first query.
+    pub height: FvmQueryHeight,
+    /// Progress reporting for the export.
+    pub progress: Progress,
+}
+
+/// Object store import options.
+#[derive(Clone, Debug, Default)]
+pub struct ImportOptions {
+    /// Overwrite objects that already exist.
+    pub overwrite: bool,
+    /// Broadcast mode for the transactions.
+    pub broadcast_mode: BroadcastMode,
+    /// Gas params for the transactions.
+    pub gas_params: GasParams,
+    /// Progress reporting for the import.
+    pub progress: Progress,
+}
+
+/// Summary of an object store export.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportSummary {
+    /// CID of the manifest, which is the root of the archive.
+    pub root: Cid,
+    /// Block height at which the objects were exported.
+    pub height: u64,
+    /// Number of objects exported.
+    pub objects: usize,
+    /// Total size of the exported objects in bytes.
+    pub size: usize,
+}
+
+/// Summary of an object store import.
+#[derive(Clone, Debug, Serialize)]
+pub struct ImportSummary {
+    /// CID of the archive's manifest.
+    pub root: Cid,
+    /// Block height recorded in the manifest.
+    pub height: u64,
+    /// Number of objects added.
+    pub objects: usize,
+    /// Total size of the added objects in bytes.
+    pub size: usize,
+}
+
+/// The root block of an exported archive, mapping keys to objects.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct Manifest {
+    /// Block height at which the objects were exported.
+    pub height: u64,
+    /// Objects by key.
+    pub objects: BTreeMap<String, ManifestEntry>,
+    /// Manifest format version.
+    pub version: u64,
+}
+
+/// An object in a [`Manifest`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct ManifestEntry {
+    /// Root CID of the object's UnixFS blocks.
+    pub cid: cid::Cid,
+    /// Object size in bytes.
+    pub size: usize,
+    /// Object metadata.
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// CARv1 header.
+#[derive(Serialize, Deserialize)]
+struct CarHeader {
+    roots: Vec<cid::Cid>,
+    version: u64,
+}
+
+/// Writes blocks to a CARv1 archive.
+pub(crate) struct CarWriter<W> {
+    writer: W,
+}
+
+impl<W: AsyncWrite + Unpin> CarWriter<W> {
+    /// Create a writer and write the archive header with a single root.
+    pub async fn new(mut writer: W, root: cid::Cid) -> anyhow::Result<Self> {
+        let header = fvm_ipld_encoding::to_vec(&CarHeader {
+            roots: vec![root],
+            version: 1,
+        })?;
+        writer.write_all(&encode_varint(header.len())).await?;
+        writer.write_all(&header).await?;
+        Ok(Self { writer })
+    }
+
+    /// Write a block to the archive.
+    pub async fn write_block(&mut self, cid: &cid::Cid, data: &[u8]) -> anyhow::Result<()> {
+        let cid = cid.to_bytes();
+        self.writer
+            .write_all(&encode_varint(cid.len() + data.len()))
+            .await?;
+        self.writer.write_all(&cid).await?;
+        self.writer.write_all(data).await?;
+        Ok(())
+    }
+
+    /// Flush the archive and return the underlying writer.
+    pub async fn finish(mut self) -> anyhow::Result<W> {
+        self.writer.flush().await?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads blocks from a CARv1 archive by CID.
+pub(crate) struct CarReader<R> {
+    reader: R,
+    roots: Vec<cid::Cid>,
+    /// Offset and length of each block's data, which lie within the archive.
+    blocks: HashMap<cid::Cid, (u64, usize)>,
+}
+
+impl<R: AsyncRead + AsyncSeek + Unpin> CarReader<R> {
+    /// Create a reader by reading the archive header and indexing its blocks.
+    ///
+    /// Section lengths are checked against the archive's size before anything is allocated
+    /// for them, so a corrupt length fails instead of exhausting memory.
+    pub async fn new(mut reader: R) -> anyhow::Result<Self> {
+        let start = reader.stream_position().await?;
+        let end = reader.seek(SeekFrom::End(0)).await?;
+        reader.seek(SeekFrom::Start(start)).await?;
+
+        let len = read_varint(&mut reader)
+            .await?
+            .ok_or_else(|| anyhow!("invalid car archive; missing header"))?;
+        if len as u64 > end - reader.stream_position().await? {
+            return Err(anyhow!("invalid car archive; truncated header"));
+        }
+        let mut header = vec![0; len];
+        reader.read_exact(&mut header).await?;
+        let header: CarHeader = fvm_ipld_encoding::from_slice(&header)
+            .map_err(|e| anyhow!("invalid car archive; error parsing header: {e}"))?;
+        if header.version != 1 {
+            return Err(anyhow!(
+                "unsupported car archive version {}",
+                header.version
+            ));
+        }
+
+        let mut blocks = HashMap::new();
+        while let Some(len) = read_varint(&mut reader).await? {
+            let start = reader.stream_position().await?;
+            if len as u64 > end - start {
+                return Err(anyhow!("invalid car archive; truncated block"));
+            }
+            // CIDs are short, so read just enough of the section to parse one
+            let mut prefix = vec![0; len.min(128)];
+            reader.read_exact(&mut prefix).await?;
+            let mut cursor = Cursor::new(prefix.as_slice());
+            let cid = cid::Cid::read_bytes(&mut cursor)
+                .map_err(|e| anyhow!("invalid car archive; error parsing cid: {e}"))?;
+            let cid_len = cursor.position() as usize;
+            blocks.insert(cid, (start + cid_len as u64, len - cid_len));
+            reader.seek(SeekFrom::Start(start + len as u64)).await?;
+        }
+        Ok(Self {
+            reader,
+            roots: header.roots,
+            blocks,
+        })
+    }
+
+    /// Returns the archive's root CIDs.
+    pub fn roots(&self) -> &[cid::Cid] {
+        &self.roots
+    }
+
+    /// Read the data of a block.
+    pub async fn get(&mut self, cid: &cid::Cid) -> anyhow::Result<Vec<u8>> {
+        let (offset, len) = *self
+            .blocks
+            .get(cid)
+            .ok_or_else(|| anyhow!("block {} not found in car archive", cid))?;
+        let mut data = vec![0; len];
+        self.reader.seek(SeekFrom::Start(offset)).await?;
+        self.reader.read_exact(&mut data).await?;
+        Ok(data)
+    }
+}
+
+impl Manifest {
+    /// Encode the manifest as a DAG-CBOR block, returning its CID and bytes.
+    pub fn encode(&self) -> anyhow::Result<(cid::Cid, Vec<u8>)> {
+        let data = fvm_ipld_encoding::to_vec(self)?;
+        let hash = Multihash::wrap(SHA2_256, &Sha256::digest(&data))?;
+        Ok((cid::Cid::new_v1(DAG_CBOR, hash), data))
+    }
+
+    /// Decode a manifest from a DAG-CBOR block, verifying the block against its CID.
+    pub fn decode(cid: &cid::Cid, data: &[u8]) -> anyhow::Result<Self> {
+        let hash = cid.hash();
+        if cid.codec() != DAG_CBOR || hash.code() != SHA2_256 {
+            return Err(anyhow!("unsupported manifest cid {}", cid));
+        }
+        if hash.digest() != Sha256::digest(data).as_slice() {
+            return Err(anyhow!("manifest block does not match cid {}", cid));
+        }
+        let manifest: Self = fvm_ipld_encoding::from_slice(data)
+            .map_err(|e| anyhow!("error parsing manifest: {e}"))?;
+        if manifest.version != MANIFEST_VERSION {
+            return Err(anyhow!("unsupported manifest version {}", manifest.version));
+        }
+        Ok(manifest)
+    }
+}
+
+impl ObjectStore {
+    /// Export the objects under a prefix to a CARv1 archive.
+    ///
+    /// The archive's root is a DAG-CBOR manifest mapping keys to object CIDs, sizes, and
+    /// metadata, followed by the UnixFS blocks of every object. Objects are exported as stored
+    /// and each one is verified against its CID while it's written. All objects must be resolved.
+    pub async fn export<W>(
+        &self,
+        provider: &(impl QueryProvider + ObjectProvider),
+        writer: W,
+        options: ExportOptions,
+    ) -> anyhow::Result<ExportSummary>
+    where
+        W: AsyncWrite + Unpin + Send,
+    {
+        let started = Instant::now();
+        let progress = &options.progress;
+
+        progress.step(1, 2);
+        progress.status("Listing objects...");
+        let (objects, height) = self
+            .list_all(provider, &options.prefix, options.height)
+            .await?;
+        let mut manifest = Manifest {
+            height,
+            objects: BTreeMap::new(),
+            version: MANIFEST_VERSION,
+        };
+        for (key, object) in objects {
+            if !object.resolved {
+                return Err(anyhow!("object '{}' is not resolved", key));
+            }
+            let entry = ManifestEntry {
+                cid: cid::Cid::try_from(object.cid.0)?,
+                size: object.size,
+                metadata: object.metadata.into_iter().collect(),
+            };
+            manifest.objects.insert(key, entry);
+        }
+        let (root, data) = manifest.encode()?;
+        let mut car = CarWriter::new(writer, root).await?;
+        car.write_block(&root, &data).await?;
+
+        progress.step(2, 2);
+        let mut written = HashSet::new();
+        let mut size = 0;
+        for (key, entry) in &manifest.objects {
+            progress.report(ProgressEvent::DownloadStarted {
+                cid: Cid::from(entry.cid),
+                size: entry.size,
+                offset: 0,
+            });
+            progress.status(format!("Exporting {}...", key));
+            let response = provider.download(self.address, key, None, height).await?;
+            let mut stream = response.bytes_stream();
+            let mut builder = CidBuilder::new();
+            while let Some(chunk) = stream.next().await {
+                for (c, block) in builder.push_blocks(&chunk?)? {
+                    if written.insert(c) {
+                        car.write_block(&c.0, &block).await?;
+                    }
+                }
+                progress.report(ProgressEvent::Downloaded {
+                    bytes: builder.size(),
+                });
+            }
+            size += builder.size();
+            let (blocks, object_cid) = builder.finish_blocks()?;
+            for (c, block) in blocks {
+                if written.insert(c) {
+                    car.write_block(&c.0, &block).await?;
+                }
+            }
+            progress.report(ProgressEvent::DownloadFinished);
+            if object_cid.0 != entry.cid {
+                return Err(anyhow!(
+                    "cannot verify object '{}'; downloaded cid {} does not match {}",
+                    key,
+                    object_cid,
+                    entry.cid
+                ));
+            }
+        }
+        car.finish().await?;
+
+        let root = Cid::from(root);
+        progress.finished(format!(
+            "Exported {} objects in {} (root={})",
+            manifest.objects.len(),
+            HumanDuration(started.elapsed()),
+            root
+        ));
+        Ok(ExportSummary {
+            root,
+            height,
+            objects: manifest.objects.len(),
+            size,
+        })
+    }
+
+    /// Import the objects in a CARv1 archive written by [`ObjectStore::export`].
+    ///
+    /// Each object is reassembled from the archive's UnixFS blocks and added under its original
+    /// key with its original metadata. The CID of the reassembled bytes is checked against the
+    /// manifest before anything is broadcasted, so objects are imported as stored, e.g.,
+    /// compressed or encrypted objects stay that way. Objects are added one at a time in key order.
+    pub async fn import<C>(
+        &self,
+        provider: &impl Provider<C>,
+        signer: &mut impl Signer,
+        path: impl AsRef<Path>,
+        options: ImportOptions,
+    ) -> anyhow::Result<ImportSummary>
+    where
+        C: Client + Send + Sync,
+    {
+        let started = Instant::now();
+        let progress = &options.progress;
+
+        progress.step(1, 2);
+        progress.status("Reading archive...");
+        let file = File::open(path).await?;
+        let mut car = CarReader::new(BufReader::new(file)).await?;
+        let root = match car.roots() {
+            [root] => *root,
+            _ => return Err(anyhow!("car archive must have a single root")),
+        };
+        let manifest = Manifest::decode(&root, &car.get(&root).await?)?;
+
+        // Check every object before broadcasting any of them
+        let total = manifest.objects.len();
+        for (i, (key, entry)) in manifest.objects.iter().enumerate() {
+            progress.status(format!("Checking {}/{} objects ({})", i + 1, total, key));
+            reassemble(&mut car, key, entry, tokio::io::sink()).await?;
+        }
+
+        progress.step(2, 2);
+        let mut size = 0;
+        for (i, (key, entry)) in manifest.objects.iter().enumerate() {
+            progress.status(format!("Adding {}/{} objects ({})", i + 1, total, key));
+            let mut file = TempFile::new().await?;
+            reassemble(&mut car, key, entry, &mut file).await?;
+            file.rewind().await?;
+            let add_options = AddOptions {
+                overwrite: options.overwrite,
+                broadcast_mode: options.broadcast_mode,
+                gas_params: options.gas_params.clone(),
+                metadata: entry.metadata.clone().into_iter().collect(),
+                ..Default::default()
+            };
+            self.add(provider, signer, key, file, add_options).await?;
+            size += entry.size;
+        }
+
+        let root = Cid::from(root);
+        progress.finished(format!(
+            "Imported {} objects in {} (root={})",
+            total,
+            HumanDuration(started.elapsed()),
+            root
+        ));
+        Ok(ImportSummary {
+            root,
+            height: manifest.height,
+            objects: total,
+            size,
+        })
+    }
+}
+
+/// Reassemble an object from its UnixFS blocks into a writer,
+/// checking the bytes against the object's CID.
+async fn reassemble<R, W>(
+    car: &mut CarReader<R>,
+    key: &str,
+    entry: &ManifestEntry,
+    mut writer: W,
+) -> anyhow::Result<()>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+    W: AsyncWrite + Unpin,
+{
+    let mut builder = CidBuilder::new();
+    let read_failed = |e| anyhow!("cannot read object '{}': {}", key, e);
+
+    let block = car.get(&entry.cid).await?;
+    let (content, _, _, mut step) = IdleFileVisit::default()
+        .start(&block)
+        .map_err(read_failed)?;
+    builder.push(content)?;
+    writer.write_all(content).await?;
+    let mut cache = None;
+    while let Some(visit) = step {
+        let (next, _) = visit.pending_links();
+        let block = car.get(&cid::Cid::try_from(next.to_bytes())?).await?;
+        let (content, next_step) = visit
+            .continue_walk(&block, &mut cache)
+            .map_err(read_failed)?;
+        builder.push(content)?;
+        writer.write_all(content).await?;
+        step = next_step;
+    }
+    writer.flush().await?;
+
+    let cid = builder.finish()?;
+    if cid.0 != entry.cid {
+        return Err(anyhow!(
+            "cannot verify object '{}'; archived cid {} does not match {}",
+            key,
+            cid,
+            entry.cid
+        ));
+    }
+    Ok(())
+}
+
+/// Read an unsigned LEB128 varint, returning `None` at the end of the reader.
+async fn read_varint<R: AsyncRead + Unpin>(reader: &mut R) -> anyhow::Result<Option<usize>> {
+    let mut n = 0;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0; 1];
+        if reader.read(&mut byte).await? == 0 {
+            if shift == 0 {
+                return Ok(None);
+            }
+            return Err(anyhow!("invalid car archive; truncated varint"));
+        }
+        if shift > 56 {
+            return Err(anyhow!("invalid car archive; varint overflow"));
+        }
+        n |= ((byte[0] & 0x7f) as usize) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(Some(n));
+        }
+        shift += 7;
+    }
+}
+
+/// Encode an unsigned LEB128 varint, as used for CAR section lengths.
+fn encode_varint(mut n: usize) -> Vec<u8> {
+    let mut buf = Vec::new();
+    loop {
+        let byte = (n & 0x7f) as u8;
+        n >>= 7;
+        if n == 0 {
+            buf.push(byte);
+            return buf;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::CHUNK_SIZE;
+    use super::*;
+
+    #[tokio::test]
+    async fn test_car_roundtrip() {
+        let data: Vec<u8> = (0..(CHUNK_SIZE * 2 + 1234)).map(|i| i as u8).collect();
+        let mut builder = CidBuilder::new();
+        let mut blocks = builder.push_blocks(&data).unwrap();
+        let (rest, object_cid) = builder.finish_blocks().unwrap();
+        blocks.extend(rest);
+
+        let manifest = Manifest {
+            height: 1,
+            objects: BTreeMap::from([(
+                "foo/bar".to_string(),
+                ManifestEntry {
+                    cid: object_cid.0,
+                    size: data.len(),
+                    metadata: BTreeMap::from([("foo".to_string(), "bar".to_string())]),
+                },
+            )]),
+            version: MANIFEST_VERSION,
+        };
+        let (root, manifest_data) = manifest.encode().unwrap();
+        let mut car = CarWriter::new(Vec::new(), root).await.unwrap();
+        car.write_block(&root, &manifest_data).await.unwrap();
+        for (c, block) in blocks {
+            car.write_block(&c.0, &block).await.unwrap();
+        }
+        let archive = car.finish().await.unwrap();
+
+        let mut car = CarReader::new(Cursor::new(archive)).await.unwrap();
+        assert_eq!(car.roots(), &[root]);
+        let decoded = Manifest::decode(&root, &car.get(&root).await.unwrap()).unwrap();
+        let entry = &decoded.objects["foo/bar"];
+        assert_eq!(entry.cid, object_cid.0);
+
+        let mut reassembled = Vec::new();
+        reassemble(&mut car, "foo/bar", entry, &mut reassembled)
+            .await
+            .unwrap();
+        assert_eq!(reassembled, data);
+    }
+
+    #[tokio::test]
+    async fn test_car_invalid_lengths() {
+        let (root, manifest_data) = Manifest {
+            height: 1,
+            objects: BTreeMap::new(),
+            version: MANIFEST_VERSION,
+        }
+        .encode()
+        .unwrap();
+        let mut car = CarWriter::new(Vec::new(), root).await.unwrap();
+        car.write_block(&root, &manifest_data).await.unwrap();
+        let archive = car.finish().await.unwrap();
+        CarReader::new(Cursor::new(archive.clone())).await.unwrap();
+
+        // A header length past the end of the archive
+        let mut oversized = encode_varint(usize::MAX >> 8);
+        oversized.extend_from_slice(&archive[1..]);
+        let err = CarReader::new(Cursor::new(oversized)).await.err().unwrap();
+        assert_eq!(err.to_string(), "invalid car archive; truncated header");
+
+        // A block cut short
+        let truncated = archive[..archive.len() - 1].to_vec();
+        let err = CarReader::new(Cursor::new(truncated)).await.err().unwrap();
+        assert_eq!(err.to_string(), "invalid car archive; truncated block");
+
+        // A block length past the end of the archive
+        let mut oversized = archive.clone();
+        oversized.extend(encode_varint(usize::MAX >> 8));
+        oversized.extend_from_slice(&manifest_data);
+        let err = CarReader::new(Cursor::new(oversized)).await.err().unwrap();
+        assert_eq!(err.to_string(), "invalid car archive; truncated block");
+    }
+}
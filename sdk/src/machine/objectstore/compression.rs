@@ -0,0 +1,163 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+use async_compression::tokio::{
+    bufread::{GzipEncoder, ZstdEncoder},
+    write::{GzipDecoder, ZstdDecoder},
+};
+use tokio::io::{AsyncRead, AsyncWrite, BufReader};
+
+use crate::progress::Progress;
+
+/// Metadata key holding the compression applied to an object's content.
+pub const CONTENT_ENCODING_KEY: &str = "content-encoding";
+
+/// Compression applied to an object's content before it's uploaded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// Gzip compression.
+    Gzip,
+    /// Zstandard compression.
+    Zstd,
+}
+
+impl Compression {
+    /// Returns the compression recorded in object metadata, if any.
+    pub fn from_metadata(metadata: &HashMap<String, String>) -> anyhow::Result<Option<Self>> {
+        metadata
+            .get(CONTENT_ENCODING_KEY)
+            .map(|encoding| encoding.parse())
+            .transpose()
+    }
+
+    /// Returns the compression to decode when downloading an object, if any.
+    ///
+    /// Encodings this SDK can't decode, e.g., `br` or `identity` set by other clients, are
+    /// treated as opaque: the stored bytes are returned as they are, with a warning.
+    pub(crate) fn for_download(
+        metadata: &HashMap<String, String>,
+        progress: &Progress,
+    ) -> Option<Self> {
+        match Self::from_metadata(metadata) {
+            Ok(compression) => compression,
+            Err(e) => {
+                progress.warning(format!("{}; writing the stored bytes as they are", e));
+                None
+            }
+        }
+    }
+
+    /// Returns the metadata recording this compression.
+    pub(crate) fn metadata(&self) -> HashMap<String, String> {
+        HashMap::from([(CONTENT_ENCODING_KEY.to_string(), self.to_string())])
+    }
+
+    /// Wrap a reader so that it yields compressed bytes.
+    pub(crate) fn encoder<'a, R>(&self, reader: R) -> Pin<Box<dyn AsyncRead + Send + 'a>>
+    where
+        R: AsyncRead + Unpin + Send + 'a,
+    {
+        let reader = BufReader::new(reader);
+        match self {
+            Compression::Gzip => Box::pin(GzipEncoder::new(reader)),
+            Compression::Zstd => Box::pin(ZstdEncoder::new(reader)),
+        }
+    }
+
+    /// Wrap a writer so that compressed bytes written to it are decompressed.
+    /// The writer must be shut down to finish decoding.
+    pub(crate) fn decoder<W>(&self, writer: W) -> Pin<Box<dyn AsyncWrite + Send>>
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        match self {
+            Compression::Gzip => Box::pin(GzipDecoder::new(writer)),
+            Compression::Zstd => Box::pin(ZstdDecoder::new(writer)),
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::Gzip => write!(f, "gzip"),
+            Compression::Zstd => write!(f, "zstd"),
+        }
+    }
+}
+
+impl FromStr for Compression {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            _ => Err(anyhow!("unsupported content encoding '{}'", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        sync::mpsc,
+    };
+
+    use super::*;
+    use crate::progress::ProgressEvent;
+
+    #[tokio::test]
+    async fn test_compression_roundtrip() {
+        let data = "id,name\n1,foo\n2,bar\n".repeat(1000).into_bytes();
+        for compression in [Compression::Gzip, Compression::Zstd] {
+            let metadata = compression.metadata();
+            assert_eq!(
+                Compression::from_metadata(&metadata).unwrap(),
+                Some(compression)
+            );
+
+            let mut compressed = Vec::new();
+            compression
+                .encoder(data.as_slice())
+                .read_to_end(&mut compressed)
+                .await
+                .unwrap();
+            assert!(compressed.len() < data.len());
+
+            let (mut reader, writer) = tokio::io::duplex(data.len() * 2);
+            let mut decoder = compression.decoder(writer);
+            decoder.write_all(&compressed).await.unwrap();
+            decoder.shutdown().await.unwrap();
+            drop(decoder);
+            let mut decompressed = Vec::new();
+            reader.read_to_end(&mut decompressed).await.unwrap();
+            assert_eq!(decompressed, data);
+        }
+    }
+
+    #[test]
+    fn test_unknown_encoding_is_opaque() {
+        let metadata = HashMap::from([(CONTENT_ENCODING_KEY.to_string(), "br".to_string())]);
+        assert!(Compression::from_metadata(&metadata).is_err());
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let progress = Progress::new(tx);
+        assert_eq!(Compression::for_download(&metadata, &progress), None);
+        assert!(matches!(rx.try_recv(), Ok(ProgressEvent::Warning(_))));
+
+        let metadata = Compression::Zstd.metadata();
+        assert_eq!(
+            Compression::for_download(&metadata, &progress),
+            Some(Compression::Zstd)
+        );
+        assert!(rx.try_recv().is_err());
+    }
+}
@@ -0,0 +1,273 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+use aes_gcm::{
+    aead::{
+        generic_array::GenericArray,
+        stream::{DecryptorBE32, EncryptorBE32},
+        KeyInit,
+    },
+    Aes256Gcm,
+};
+use anyhow::anyhow;
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Cipher recorded in the metadata of encrypted objects.
+const CIPHER: &str = "aes-256-gcm-stream";
+/// Metadata key holding the cipher.
+const ENCRYPTION_KEY: &str = "encryption";
+/// Metadata key holding the ID of the encryption key.
+const KEY_ID_KEY: &str = "encryption-key-id";
+/// Metadata key holding the hex-encoded stream nonce prefix.
+const NONCE_KEY: &str = "encryption-nonce";
+/// Metadata key holding the plaintext segment size.
+const SEGMENT_SIZE_KEY: &str = "encryption-segment-size";
+
+/// Size of the plaintext segments that are sealed individually.
+const SEGMENT_SIZE: usize = 64 * 1024;
+/// Size of the authentication tag appended to each segment.
+const TAG_SIZE: usize = 16;
+/// Size of the nonce prefix used by the STREAM construction.
+const NONCE_SIZE: usize = 7;
+
+/// A 256-bit symmetric key used to encrypt object payloads on the client.
+#[derive(Clone)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// Generate a new random key.
+    pub fn generate() -> Self {
+        let mut key = [0; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// Create a key from 32 raw bytes.
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let key = bytes
+            .try_into()
+            .map_err(|_| anyhow!("encryption key must be 32 bytes; got {}", bytes.len()))?;
+        Ok(Self(key))
+    }
+
+    /// Read a key from a file containing either 32 raw bytes or 64 hex characters.
+    pub async fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let data = tokio::fs::read(path).await?;
+        match std::str::from_utf8(&data).map(str::trim) {
+            Ok(s) if s.len() == 64 => Self::from_bytes(&hex::decode(s)?),
+            _ => Self::from_bytes(&data),
+        }
+    }
+
+    /// Returns a short, non-secret identifier for the key.
+    /// The ID is recorded in object metadata so the right key can be found for decryption.
+    pub fn id(&self) -> String {
+        hex::encode(&Sha256::digest(self.0)[..8])
+    }
+
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(GenericArray::from_slice(&self.0))
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EncryptionKey").field(&self.id()).finish()
+    }
+}
+
+/// Returns whether object metadata marks the object as encrypted.
+pub(crate) fn is_encrypted(metadata: &HashMap<String, String>) -> bool {
+    metadata.contains_key(ENCRYPTION_KEY)
+}
+
+/// Incrementally encrypts an object payload.
+///
+/// Plaintext is sealed in fixed-size segments, so payloads of any size can be encrypted
+/// without holding them in memory.
+pub(crate) struct Encryptor {
+    stream: EncryptorBE32<Aes256Gcm>,
+    buffer: Vec<u8>,
+    metadata: HashMap<String, String>,
+}
+
+impl Encryptor {
+    /// Create an encryptor with a random nonce.
+    pub fn new(key: &EncryptionKey) -> Self {
+        let mut nonce = [0; NONCE_SIZE];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let stream = EncryptorBE32::from_aead(key.cipher(), GenericArray::from_slice(&nonce));
+        let metadata = HashMap::from([
+            (ENCRYPTION_KEY.to_string(), CIPHER.to_string()),
+            (KEY_ID_KEY.to_string(), key.id()),
+            (NONCE_KEY.to_string(), hex::encode(nonce)),
+            (SEGMENT_SIZE_KEY.to_string(), SEGMENT_SIZE.to_string()),
+        ]);
+        Self {
+            stream,
+            buffer: Vec::with_capacity(SEGMENT_SIZE),
+            metadata,
+        }
+    }
+
+    /// Returns the metadata needed to decrypt the payload.
+    pub fn metadata(&self) -> HashMap<String, String> {
+        self.metadata.clone()
+    }
+
+    /// Push plaintext into the encryptor.
+    /// Returns the ciphertext of segments that were completed by this push.
+    pub fn push(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+        let mut ciphertext = Vec::new();
+        // The last segment is sealed differently, so always hold one back
+        while self.buffer.len() > SEGMENT_SIZE {
+            let segment: Vec<u8> = self.buffer.drain(..SEGMENT_SIZE).collect();
+            let sealed = self
+                .stream
+                .encrypt_next(segment.as_slice())
+                .map_err(|_| anyhow!("failed to encrypt segment"))?;
+            ciphertext.extend(sealed);
+        }
+        Ok(ciphertext)
+    }
+
+    /// Finish the payload and return the ciphertext of the last segment.
+    pub fn finish(self) -> anyhow::Result<Vec<u8>> {
+        self.stream
+            .encrypt_last(self.buffer.as_slice())
+            .map_err(|_| anyhow!("failed to encrypt segment"))
+    }
+}
+
+/// Incrementally decrypts an object payload produced by [`Encryptor`].
+pub(crate) struct Decryptor {
+    stream: DecryptorBE32<Aes256Gcm>,
+    buffer: Vec<u8>,
+    segment_size: usize,
+}
+
+impl Decryptor {
+    /// Create a decryptor from the key and the object's metadata.
+    pub fn new(key: &EncryptionKey, metadata: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let param = |k: &str| {
+            metadata
+                .get(k)
+                .ok_or_else(|| anyhow!("cannot decrypt object; missing '{}' metadata", k))
+        };
+        let cipher = param(ENCRYPTION_KEY)?;
+        if cipher != CIPHER {
+            return Err(anyhow!(
+                "cannot decrypt object; unsupported cipher '{}'",
+                cipher
+            ));
+        }
+        let key_id = param(KEY_ID_KEY)?;
+        if *key_id != key.id() {
+            return Err(anyhow!(
+                "cannot decrypt object; object was encrypted with key {} but key {} was given",
+                key_id,
+                key.id()
+            ));
+        }
+        let nonce = hex::decode(param(NONCE_KEY)?)?;
+        if nonce.len() != NONCE_SIZE {
+            return Err(anyhow!("cannot decrypt object; invalid nonce"));
+        }
+        // The segment size bounds the buffered ciphertext, so don't trust more than we write
+        let segment_size = param(SEGMENT_SIZE_KEY)?.parse()?;
+        if segment_size == 0 || segment_size > SEGMENT_SIZE {
+            return Err(anyhow!(
+                "cannot decrypt object; invalid segment size {}",
+                segment_size
+            ));
+        }
+        let stream = DecryptorBE32::from_aead(key.cipher(), GenericArray::from_slice(&nonce));
+        Ok(Self {
+            stream,
+            buffer: Vec::new(),
+            segment_size,
+        })
+    }
+
+    /// Push ciphertext into the decryptor.
+    /// Returns the plaintext of segments that were completed by this push.
+    pub fn push(&mut self, data: &[u8]) -> anyhow::Result<Vec<u8>> {
+        self.buffer.extend_from_slice(data);
+        let sealed_size = self.segment_size + TAG_SIZE;
+        let mut plaintext = Vec::new();
+        while self.buffer.len() > sealed_size {
+            let segment: Vec<u8> = self.buffer.drain(..sealed_size).collect();
+            let opened = self
+                .stream
+                .decrypt_next(segment.as_slice())
+                .map_err(|_| anyhow!("cannot decrypt object; segment failed authentication"))?;
+            plaintext.extend(opened);
+        }
+        Ok(plaintext)
+    }
+
+    /// Finish the payload and return the plaintext of the last segment.
+    pub fn finish(self) -> anyhow::Result<Vec<u8>> {
+        self.stream
+            .decrypt_last(self.buffer.as_slice())
+            .map_err(|_| anyhow!("cannot decrypt object; segment failed authentication"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encryption_roundtrip() {
+        let key = EncryptionKey::generate();
+        let data: Vec<u8> = (0..(SEGMENT_SIZE * 3 + 17)).map(|i| i as u8).collect();
+
+        let mut encryptor = Encryptor::new(&key);
+        let mut ciphertext = Vec::new();
+        for piece in data.chunks(10_000) {
+            ciphertext.extend(encryptor.push(piece).unwrap());
+        }
+        let metadata = encryptor.metadata();
+        ciphertext.extend(encryptor.finish().unwrap());
+        assert!(is_encrypted(&metadata));
+        assert_eq!(ciphertext.len(), data.len() + 4 * TAG_SIZE);
+
+        let mut decryptor = Decryptor::new(&key, &metadata).unwrap();
+        let mut plaintext = Vec::new();
+        for piece in ciphertext.chunks(30_000) {
+            plaintext.extend(decryptor.push(piece).unwrap());
+        }
+        plaintext.extend(decryptor.finish().unwrap());
+        assert_eq!(plaintext, data);
+
+        let other = EncryptionKey::generate();
+        assert!(Decryptor::new(&other, &metadata).is_err());
+
+        let mut truncated = Decryptor::new(&key, &metadata).unwrap();
+        truncated.push(&ciphertext[..SEGMENT_SIZE * 2]).unwrap();
+        assert!(truncated.finish().is_err());
+    }
+
+    #[test]
+    fn test_decryptor_segment_size() {
+        let key = EncryptionKey::generate();
+        let mut metadata = Encryptor::new(&key).metadata();
+        for size in ["0", "65537", "18446744073709551615"] {
+            metadata.insert(SEGMENT_SIZE_KEY.to_string(), size.to_string());
+            let err = Decryptor::new(&key, &metadata).err().unwrap();
+            assert_eq!(
+                err.to_string(),
+                format!("cannot decrypt object; invalid segment size {}", size)
+            );
+        }
+        metadata.insert(SEGMENT_SIZE_KEY.to_string(), "1024".to_string());
+        assert!(Decryptor::new(&key, &metadata).is_ok());
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::io::SeekFrom;
+use std::path::Path;
+use std::time::SystemTime;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use fvm_shared::address::Address;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeek, AsyncSeekExt};
+
+use adm_provider::response::Cid;
+
+use super::unixfs::CHUNK_SIZE;
+
+/// Local record of an object add in progress.
+///
+/// The journal lets a retried add skip re-hashing the input and re-uploading an object the
+/// Object API has already acknowledged.
+/// Uploads are acknowledged as a whole, so an upload that was cut off is sent again in full.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct UploadJournal {
+    /// Object store machine address.
+    pub address: String,
+    /// Key of the object being added.
+    pub key: String,
+    /// Size of the input in bytes.
+    pub source_size: u64,
+    /// Modification time of the input, if known.
+    pub source_modified: Option<SystemTime>,
+    /// Hash of the input's size and its first and last chunks.
+    pub fingerprint: String,
+    /// Locally computed object CID.
+    pub cid: Option<Cid>,
+    /// Object size reported by the chunker.
+    pub object_size: usize,
+    /// Whether the Object API has acknowledged the object.
+    pub uploaded: bool,
+}
+
+impl UploadJournal {
+    /// Create an empty journal for the given object and input.
+    pub fn new(
+        address: Address,
+        key: &str,
+        source_size: u64,
+        source_modified: Option<SystemTime>,
+        fingerprint: String,
+    ) -> Self {
+        Self {
+            address: address.to_string(),
+            key: key.into(),
+            source_size,
+            source_modified,
+            fingerprint,
+            cid: None,
+            object_size: 0,
+            uploaded: false,
+        }
+    }
+
+    /// Returns whether this journal describes the same object and input as another.
+    pub fn matches(&self, other: &UploadJournal) -> bool {
+        self.address == other.address
+            && self.key == other.key
+            && self.source_size == other.source_size
+            && self.source_modified == other.source_modified
+            && self.fingerprint == other.fingerprint
+    }
+
+    /// Returns whether the Object API has acknowledged the object.
+    pub fn is_uploaded(&self) -> bool {
+        self.cid.is_some() && self.uploaded
+    }
+}
+
+/// Returns a cheap fingerprint of a seekable input, used to detect an input that was edited
+/// between attempts without changing its size.
+///
+/// The reader is rewound to the start.
+pub(crate) async fn fingerprint<R>(reader: &mut R, size: u64) -> anyhow::Result<String>
+where
+    R: AsyncRead + AsyncSeek + Unpin,
+{
+    let chunk = size.min(CHUNK_SIZE as u64) as usize;
+    let mut buffer = vec![0; chunk];
+    let mut hasher = Sha256::new();
+    hasher.update(size.to_le_bytes());
+
+    reader.rewind().await?;
+    reader.read_exact(&mut buffer).await?;
+    hasher.update(&buffer);
+    reader.seek(SeekFrom::Start(size - chunk as u64)).await?;
+    reader.read_exact(&mut buffer).await?;
+    hasher.update(&buffer);
+    reader.rewind().await?;
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+impl Journal for UploadJournal {}
+
+/// Local record of an object download in progress.
+///
+/// The journal pins the query height of the first attempt so that a resumed download
+/// does not mix bytes from two versions of the same key.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DownloadJournal {
+    /// Object store machine address.
+    pub address: String,
+    /// Key of the object being downloaded.
+    pub key: String,
+    /// Object CID at the pinned height.
+    pub cid: Cid,
+    /// Pinned query height.
+    pub height: u64,
+}
+
+impl DownloadJournal {
+    /// Create a journal for the given object.
+    pub fn new(address: Address, key: &str, cid: Cid, height: u64) -> Self {
+        Self {
+            address: address.to_string(),
+            key: key.into(),
+            cid,
+            height,
+        }
+    }
+
+    /// Returns whether this journal describes the given object.
+    pub fn matches(&self, address: Address, key: &str) -> bool {
+        self.address == address.to_string() && self.key == key
+    }
+}
+
+impl Journal for DownloadJournal {}
+
+/// A journal persisted as JSON at a local path.
+#[async_trait]
+pub(crate) trait Journal: Serialize + DeserializeOwned + Send + Sync {
+    /// Load a journal from the given path if it exists.
+    async fn load(path: &Path) -> anyhow::Result<Option<Self>> {
+        match tokio::fs::read(path).await {
+            Ok(data) => {
+                let journal = serde_json::from_slice(&data)
+                    .with_context(|| format!("failed to parse journal {}", path.display()))?;
+                Ok(Some(journal))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write the journal to the given path.
+    async fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let data = serde_json::to_vec(self)?;
+        tokio::fs::write(path, data)
+            .await
+            .with_context(|| format!("failed to write journal {}", path.display()))
+    }
+
+    /// Remove the journal at the given path if it exists.
+    async fn remove(path: &Path) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(path).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_journal_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("adm-journal-{}", std::process::id()));
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let path = dir.join("upload.json");
+        let address = Address::new_id(1001);
+
+        assert_eq!(UploadJournal::load(&path).await.unwrap(), None);
+
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_700_000_000);
+        let mut journal = UploadJournal::new(address, "foo/bar", 42, Some(modified), "abc".into());
+        journal.cid = Some(
+            Cid::from_str("bafybeidm37d6cxxoyu5fpadpuycta2wenno6ogmzi7uh3gsfd4e4c6tyda").unwrap(),
+        );
+        journal.object_size = 42;
+        journal.save(&path).await.unwrap();
+
+        let loaded = UploadJournal::load(&path).await.unwrap().unwrap();
+        assert_eq!(loaded, journal);
+        let fresh = UploadJournal::new(address, "foo/bar", 42, Some(modified), "abc".into());
+        assert!(loaded.matches(&fresh));
+        let renamed = UploadJournal::new(address, "foo/baz", 42, Some(modified), "abc".into());
+        assert!(!loaded.matches(&renamed));
+        let touched = UploadJournal::new(address, "foo/bar", 42, None, "abc".into());
+        assert!(!loaded.matches(&touched));
+        let edited = UploadJournal::new(address, "foo/bar", 42, Some(modified), "abd".into());
+        assert!(!loaded.matches(&edited));
+        assert!(!loaded.is_uploaded());
+
+        UploadJournal::remove(&path).await.unwrap();
+        assert_eq!(UploadJournal::load(&path).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_fingerprint() {
+        let data: Vec<u8> = (0..(CHUNK_SIZE * 3)).map(|i| i as u8).collect();
+        let mut reader = std::io::Cursor::new(data.clone());
+        let original = fingerprint(&mut reader, data.len() as u64).await.unwrap();
+        assert_eq!(reader.position(), 0);
+
+        // Edits to the first or last chunk change the fingerprint
+        let mut edited = data.clone();
+        edited[data.len() - 1] ^= 1;
+        let mut reader = std::io::Cursor::new(edited);
+        assert_ne!(
+            fingerprint(&mut reader, data.len() as u64).await.unwrap(),
+            original
+        );
+
+        // Inputs smaller than a chunk are hashed in full
+        let mut reader = std::io::Cursor::new(b"hello".to_vec());
+        let small = fingerprint(&mut reader, 5).await.unwrap();
+        let mut reader = std::io::Cursor::new(b"hellp".to_vec());
+        assert_ne!(fingerprint(&mut reader, 5).await.unwrap(), small);
+    }
+}
@@ -0,0 +1,187 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+//! In-memory object store state for testing code built on [`ObjectStore`](super::ObjectStore).
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use anyhow::anyhow;
+use async_trait::async_trait;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use fendermint_actor_objectstore::{
+    GetParams, ListParams,
+    Method::{GetObject, ListObjects},
+    Object, ObjectList,
+};
+use fendermint_vm_message::query::{FvmQuery, FvmQueryHeight};
+use fvm_ipld_encoding::{BytesDe, BytesSer};
+use prost::Message as ProstMessage;
+use serde::{de::DeserializeOwned, Serialize};
+use tendermint_proto::abci::ResponseDeliverTx;
+use tendermint_rpc::endpoint::abci_query::AbciQuery;
+
+use adm_provider::{query::QueryProvider, response::Cid};
+
+use super::unixfs::CidBuilder;
+
+/// Query provider that answers `GetObject` and `ListObjects` calls from in-memory states.
+///
+/// Listing follows the actor: the offset and limit only apply to objects, and common
+/// prefixes are collected from every key visited, so they repeat on every page.
+pub struct MockQueryProvider {
+    /// Store contents by the height from which they apply.
+    states: BTreeMap<u64, BTreeMap<String, Object>>,
+    /// Latest height, which advances with every query that isn't pinned to a height.
+    latest: Mutex<u64>,
+    /// Heights of the queries received, in order.
+    pub queries: Mutex<Vec<u64>>,
+}
+
+impl MockQueryProvider {
+    pub fn new(latest: u64) -> Self {
+        MockQueryProvider {
+            states: BTreeMap::new(),
+            latest: Mutex::new(latest),
+            queries: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Set the store contents from the given height on.
+    pub fn set(&mut self, height: u64, objects: &[(&str, Object)]) {
+        let state = objects
+            .iter()
+            .map(|(key, object)| (key.to_string(), object.clone()))
+            .collect();
+        self.states.insert(height, state);
+    }
+
+    fn state_at(&self, height: u64) -> BTreeMap<String, Object> {
+        self.states
+            .range(..=height)
+            .next_back()
+            .map(|(_, state)| state.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[async_trait]
+impl QueryProvider for MockQueryProvider {
+    async fn query(&self, query: FvmQuery, height: FvmQueryHeight) -> anyhow::Result<AbciQuery> {
+        let height = match height {
+            FvmQueryHeight::Height(height) => height,
+            _ => {
+                let mut latest = self.latest.lock().unwrap();
+                *latest += 1;
+                *latest - 1
+            }
+        };
+        self.queries.lock().unwrap().push(height);
+
+        let FvmQuery::Call(message) = query else {
+            return Err(anyhow!("unsupported query"));
+        };
+        let state = self.state_at(height);
+        let data = if message.method_num == GetObject as u64 {
+            let params: GetParams = message.params.deserialize()?;
+            let key = String::from_utf8(bytes_of(&params.key))?;
+            fvm_ipld_encoding::to_vec(&state.get(&key))?
+        } else if message.method_num == ListObjects as u64 {
+            let params: ListParams = message.params.deserialize()?;
+            fvm_ipld_encoding::to_vec(&list(&state, params)?)?
+        } else {
+            return Err(anyhow!("unsupported method {}", message.method_num));
+        };
+
+        let deliver_tx = ResponseDeliverTx {
+            data: data.into(),
+            ..Default::default()
+        };
+        let value = fvm_ipld_encoding::to_vec(&deliver_tx.encode_to_vec())?;
+        let response = serde_json::json!({
+            "code": 0,
+            "log": "",
+            "info": "",
+            "index": "0",
+            "key": null,
+            "value": STANDARD.encode(value),
+            "proofOps": null,
+            "height": height.to_string(),
+            "codespace": "",
+        });
+        Ok(serde_json::from_value(response)?)
+    }
+}
+
+fn list(state: &BTreeMap<String, Object>, params: ListParams) -> anyhow::Result<ObjectList> {
+    let prefix = String::from_utf8(bytes_of(&params.prefix))?;
+    let delimiter = String::from_utf8(bytes_of(&params.delimiter))?;
+    let limit = match params.limit {
+        0 => usize::MAX,
+        limit => limit as usize,
+    };
+    let mut list = ObjectList {
+        objects: Vec::new(),
+        common_prefixes: Vec::new(),
+    };
+    let mut skipped = 0;
+    for (key, object) in state {
+        let Some(rest) = key.strip_prefix(&prefix) else {
+            continue;
+        };
+        if let Some(i) = rest.find(&delimiter).filter(|_| !delimiter.is_empty()) {
+            let common_prefix = key[..prefix.len() + i + delimiter.len()]
+                .as_bytes()
+                .to_vec();
+            if !list.common_prefixes.contains(&common_prefix) {
+                list.common_prefixes.push(common_prefix);
+            }
+            continue;
+        }
+        if skipped < params.offset {
+            skipped += 1;
+            continue;
+        }
+        if list.objects.len() == limit {
+            break;
+        }
+        list.objects.push((key.as_bytes().to_vec(), object.clone()));
+    }
+    Ok(list)
+}
+
+/// Create a resolved object holding `data`.
+pub fn object(data: &[u8], metadata: &[(&str, &str)]) -> Object {
+    Object {
+        cid: from_bytes(cid_of(data).0.to_bytes()),
+        size: data.len(),
+        resolved: true,
+        metadata: metadata
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+    }
+}
+
+/// Compute the CID of `data`.
+pub fn cid_of(data: &[u8]) -> Cid {
+    let mut builder = CidBuilder::new();
+    builder.push(data).unwrap();
+    builder.finish().unwrap()
+}
+
+/// Returns the bytes of a byte string parameter, whichever wrapper type holds them.
+fn bytes_of(value: &impl Serialize) -> Vec<u8> {
+    let data = fvm_ipld_encoding::to_vec(value).unwrap();
+    fvm_ipld_encoding::from_slice::<BytesDe>(&data)
+        .map(|bytes| bytes.0)
+        .or_else(|_| fvm_ipld_encoding::from_slice::<Vec<u8>>(&data))
+        .unwrap()
+}
+
+/// Builds a byte string field, whichever wrapper type holds it.
+fn from_bytes<T: DeserializeOwned>(bytes: Vec<u8>) -> T {
+    let data = fvm_ipld_encoding::to_vec(&BytesSer(&bytes)).unwrap();
+    fvm_ipld_encoding::from_slice(&data)
+        .or_else(|_| fvm_ipld_encoding::from_slice(&fvm_ipld_encoding::to_vec(&bytes)?))
+        .unwrap()
+}
@@ -0,0 +1,277 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, Context};
+use base64::{engine::general_purpose, Engine};
+use fendermint_actor_objectstore::{AddParams, Method::AddObject};
+use fendermint_vm_message::signed::{Object as MessageObject, SignedMessage};
+use fvm_ipld_encoding::RawBytes;
+use serde::{Deserialize, Serialize};
+use tendermint_rpc::Client;
+use tokio::io::{AsyncRead, AsyncSeek, AsyncSeekExt};
+
+use adm_provider::{
+    message::{object_upload_message, GasParams},
+    object::ObjectProvider,
+    response::Cid,
+    tx::{BroadcastMode, TxReceipt},
+    Provider,
+};
+use adm_signer::Signer;
+
+use crate::progress::{Progress, ProgressEvent};
+
+use super::{generate_cid, upload_progress_stream, ObjectStore};
+
+/// Object upload pre-signing options.
+#[derive(Clone, Debug, Default)]
+pub struct PresignOptions {
+    /// Overwrite the object if it already exists.
+    pub overwrite: bool,
+    /// Metadata to add to the object.
+    pub metadata: HashMap<String, String>,
+}
+
+/// A signed authorization to stage an object's content with the Object API.
+///
+/// An object store owner can create one for a key and CID with [`ObjectStore::presign`], and hand
+/// it to an untrusted worker that streams the content with [`ObjectStore::upload_presigned`]
+/// without holding the private key.
+/// The object is added to the store once the owner broadcasts its transaction with
+/// [`ObjectStore::add_presigned`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PresignedUpload {
+    /// Object store machine address.
+    pub address: String,
+    /// Key of the object.
+    pub key: String,
+    /// CID of the object's content.
+    pub cid: Cid,
+    /// Size of the object's content in bytes.
+    pub size: usize,
+    /// Whether the object overwrites an existing object.
+    pub overwrite: bool,
+    /// Metadata to add to the object.
+    pub metadata: HashMap<String, String>,
+    /// Chain ID of the subnet the message was signed for.
+    pub chain_id: u64,
+    /// Base64-encoded signed upload message, as sent to the Object API.
+    pub message: String,
+}
+
+impl ObjectStore {
+    /// Sign an upload of the object with the given CID and size for a key.
+    ///
+    /// The CID and size are computed over the object's content, e.g., with
+    /// [`compute_cid`](super::compute_cid).
+    pub fn presign(
+        &self,
+        signer: &impl Signer,
+        key: &str,
+        cid: Cid,
+        size: usize,
+        options: PresignOptions,
+    ) -> anyhow::Result<PresignedUpload> {
+        let chain_id = match signer.subnet_id() {
+            Some(id) => id.chain_id(),
+            None => {
+                return Err(anyhow!("failed to get subnet ID from signer"));
+            }
+        };
+        let params = AddParams {
+            key: key.into(),
+            cid: cid.0,
+            overwrite: options.overwrite,
+            metadata: options.metadata.clone(),
+            size,
+        };
+        let serialized_params = RawBytes::serialize(params)?;
+
+        let message = object_upload_message(
+            signer.address(),
+            self.address,
+            AddObject as u64,
+            serialized_params,
+        );
+        let signed_message = signer.sign_message(
+            message,
+            Some(MessageObject::new(key.into(), cid.0, self.address)),
+        )?;
+        let serialized_signed_message = fvm_ipld_encoding::to_vec(&signed_message)?;
+
+        Ok(PresignedUpload {
+            address: self.address.to_string(),
+            key: key.into(),
+            cid,
+            size,
+            overwrite: options.overwrite,
+            metadata: options.metadata,
+            chain_id: chain_id.into(),
+            message: general_purpose::URL_SAFE.encode(&serialized_signed_message),
+        })
+    }
+
+    /// Stage an object's content with the Object API using a pre-signed upload.
+    ///
+    /// The content is checked against the upload's CID and size before it's streamed,
+    /// so a worker cannot stage content the owner did not sign for.
+    pub async fn upload_presigned<R>(
+        &self,
+        provider: &impl ObjectProvider,
+        upload: &PresignedUpload,
+        mut reader: R,
+        progress: Progress,
+    ) -> anyhow::Result<Cid>
+    where
+        R: AsyncRead + AsyncSeek + Unpin + Send + 'static,
+    {
+        self.check_presigned(upload)?;
+
+        progress.step(1, 2);
+        let (cid, size) = generate_cid(&mut reader, &progress).await?;
+        if cid != upload.cid || size != upload.size {
+            return Err(anyhow!(
+                "content does not match the pre-signed upload (cid={}; size={})",
+                cid,
+                size
+            ));
+        }
+
+        progress.step(2, 2);
+        progress.report(ProgressEvent::UploadStarted { cid, size });
+        reader.rewind().await?;
+        let async_stream = upload_progress_stream(reader, size, progress.clone());
+        let response_cid = self.upload_stream(provider, upload, async_stream).await?;
+
+        // Verify uploaded CID with locally computed CID
+        if response_cid != cid {
+            return Err(anyhow!("cannot verify object; cid does not match remote"));
+        }
+        progress.finished(format!("Uploaded object (cid={}; size={})", cid, size));
+        Ok(cid)
+    }
+
+    /// Broadcast the transaction that adds a pre-signed object once its content is staged.
+    ///
+    /// The upload must have been signed by `signer`, and its fields must match its signed message.
+    pub async fn add_presigned<C>(
+        &self,
+        provider: &impl Provider<C>,
+        signer: &mut impl Signer,
+        upload: &PresignedUpload,
+        broadcast_mode: BroadcastMode,
+        gas_params: GasParams,
+    ) -> anyhow::Result<TxReceipt<Cid>>
+    where
+        C: Client + Send + Sync,
+    {
+        let params = self.presigned_params(signer, upload)?;
+        self.broadcast_add(provider, signer, params, broadcast_mode, gas_params)
+            .await
+    }
+
+    /// Decode the add params from a pre-signed upload's signed message.
+    ///
+    /// Returns an error if the message was not signed by `signer` for an add to this object store,
+    /// or if the upload's fields differ from the signed params.
+    fn presigned_params(
+        &self,
+        signer: &impl Signer,
+        upload: &PresignedUpload,
+    ) -> anyhow::Result<AddParams> {
+        self.check_presigned(upload)?;
+        let data = general_purpose::URL_SAFE
+            .decode(&upload.message)
+            .context("failed to decode pre-signed message")?;
+        let signed: SignedMessage = fvm_ipld_encoding::from_slice(&data)
+            .context("failed to deserialize pre-signed message")?;
+        let message = &signed.message;
+        if message.from != signer.address() {
+            return Err(anyhow!(
+                "pre-signed upload was signed by {}, not {}",
+                message.from,
+                signer.address()
+            ));
+        }
+        signer.verify_message(message, &signed.object, &signed.signature)?;
+        if message.to != self.address || message.method_num != AddObject as u64 {
+            return Err(anyhow!(
+                "pre-signed message is not an add to object store {}",
+                self.address
+            ));
+        }
+
+        let expected = AddParams {
+            key: upload.key.as_str().into(),
+            cid: upload.cid.0,
+            overwrite: upload.overwrite,
+            metadata: upload.metadata.clone(),
+            size: upload.size,
+        };
+        if RawBytes::serialize(expected)? != message.params {
+            return Err(anyhow!(
+                "pre-signed upload does not match its signed message"
+            ));
+        }
+        Ok(message.params.deserialize()?)
+    }
+
+    /// Returns an error if a pre-signed upload is for a different object store.
+    fn check_presigned(&self, upload: &PresignedUpload) -> anyhow::Result<()> {
+        if upload.address != self.address.to_string() {
+            return Err(anyhow!(
+                "pre-signed upload is for object store {}",
+                upload.address
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use fvm_shared::address::Address;
+
+    use adm_signer::{key::random_secretkey, AccountKind, SubnetID, Wallet};
+
+    use super::super::mock::cid_of;
+    use super::*;
+    use crate::machine::Machine;
+
+    fn wallet() -> Wallet {
+        let subnet_id = SubnetID::from_str("/r314159").unwrap();
+        Wallet::new_secp256k1(random_secretkey(), AccountKind::Ethereum, subnet_id).unwrap()
+    }
+
+    #[test]
+    fn test_presigned_params() {
+        let store = ObjectStore::attach(Address::new_id(1001));
+        let signer = wallet();
+        let upload = store
+            .presign(&signer, "foo", cid_of(b"foo"), 3, PresignOptions::default())
+            .unwrap();
+
+        let params = store.presigned_params(&signer, &upload).unwrap();
+        assert_eq!(params.cid, upload.cid.0);
+        assert_eq!(params.size, 3);
+
+        // Fields that differ from the signed message are rejected
+        let mut tampered = upload.clone();
+        tampered.size = 4;
+        assert!(store.presigned_params(&signer, &tampered).is_err());
+        let mut tampered = upload.clone();
+        tampered.overwrite = true;
+        assert!(store.presigned_params(&signer, &tampered).is_err());
+
+        // Uploads signed by someone else are rejected
+        assert!(store.presigned_params(&wallet(), &upload).is_err());
+
+        // Uploads for another object store are rejected
+        let other = ObjectStore::attach(Address::new_id(1002));
+        assert!(other.presigned_params(&signer, &upload).is_err());
+    }
+}
@@ -0,0 +1,609 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::pin::pin;
+
+use anyhow::{anyhow, Context};
+use fendermint_actor_objectstore::{AddParams, Object};
+use fendermint_vm_message::query::FvmQueryHeight;
+use futures::{stream, StreamExt};
+use serde::Serialize;
+use tendermint_rpc::Client;
+use tokio::{fs::File, io::AsyncSeekExt, time::Instant};
+use tokio_util::io::ReaderStream;
+
+use adm_provider::{
+    message::GasParams, object::ObjectProvider, query::QueryProvider, response::Cid,
+    tx::BroadcastMode, Provider,
+};
+use adm_signer::Signer;
+
+use crate::progress::{HumanDuration, Progress};
+
+use super::{generate_cid, DeleteOptions, GetOptions, ObjectStore, QueryItem, QueryOptions};
+
+/// Directory sync options.
+#[derive(Clone, Debug)]
+pub struct SyncOptions {
+    /// Key prefix that local paths are mapped under, e.g., "photos/".
+    /// A `/` is appended to a non-empty prefix that doesn't end with one.
+    pub prefix: String,
+    /// Delete remote keys under the prefix that no longer exist locally.
+    pub delete: bool,
+    /// Maximum number of files hashed and uploaded concurrently.
+    /// Transactions are always broadcasted one at a time to keep sequences in order.
+    pub concurrency: usize,
+    /// Broadcast mode for the transactions.
+    pub broadcast_mode: BroadcastMode,
+    /// Gas params for the transactions.
+    pub gas_params: GasParams,
+    /// Progress reporting for the sync.
+    pub progress: Progress,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        SyncOptions {
+            prefix: Default::default(),
+            delete: false,
+            concurrency: 4,
+            broadcast_mode: Default::default(),
+            gas_params: Default::default(),
+            progress: Default::default(),
+        }
+    }
+}
+
+/// Options for mirroring an object store prefix down to a local directory.
+///
+/// Objects are mirrored as stored, so compressed or encrypted objects are written as is.
+#[derive(Clone, Debug)]
+pub struct SyncDownOptions {
+    /// Key prefix to mirror, e.g., "photos/".
+    /// Keys are mapped to paths relative to the prefix using `/` as the separator.
+    /// A `/` is appended to a non-empty prefix that doesn't end with one.
+    pub prefix: String,
+    /// Query block height.
+    /// All objects are listed and downloaded at the height of the first query.
+    pub height: FvmQueryHeight,
+    /// Maximum number of objects compared and downloaded concurrently.
+    pub concurrency: usize,
+    /// Progress reporting for the sync.
+    pub progress: Progress,
+}
+
+impl Default for SyncDownOptions {
+    fn default() -> Self {
+        SyncDownOptions {
+            prefix: Default::default(),
+            height: Default::default(),
+            concurrency: 4,
+            progress: Default::default(),
+        }
+    }
+}
+
+/// Summary of a directory sync.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct SyncSummary {
+    /// Number of new objects added.
+    pub added: usize,
+    /// Number of existing objects overwritten.
+    pub updated: usize,
+    /// Number of objects that already matched the local file.
+    pub unchanged: usize,
+    /// Number of remote objects deleted.
+    pub deleted: usize,
+    /// Total bytes uploaded or downloaded.
+    pub bytes_transferred: usize,
+    /// Keys of remote objects that weren't mirrored, because they aren't resolved yet or can't be
+    /// mapped to a local path.
+    pub skipped: Vec<String>,
+}
+
+/// The outcome of preparing a single local file for sync.
+enum Staged {
+    /// The remote object already matches the local file.
+    Unchanged,
+    /// The file was uploaded and is ready to be broadcasted.
+    Uploaded(AddParams),
+}
+
+impl ObjectStore {
+    /// Sync a local directory into the object store.
+    ///
+    /// Files are compared with the remote objects under [`SyncOptions::prefix`] by size and CID,
+    /// and only new or changed files are uploaded.
+    /// Remote objects are compared as stored, so one that was compressed or encrypted when it was
+    /// added never matches its local file, and is replaced with the file's plain content.
+    pub async fn sync_up<C>(
+        &self,
+        provider: &impl Provider<C>,
+        signer: &mut impl Signer,
+        dir: impl AsRef<Path>,
+        options: SyncOptions,
+    ) -> anyhow::Result<SyncSummary>
+    where
+        C: Client + Send + Sync,
+    {
+        let started = Instant::now();
+        let progress = &options.progress;
+        let prefix = dir_prefix(&options.prefix);
+
+        progress.step(1, 3);
+        progress.status("Comparing local and remote objects...");
+        let dir = dir.as_ref();
+        let files = walk_dir(dir).await?;
+        let remote: HashMap<String, (Cid, usize)> = self
+            .list_all(provider, &prefix, FvmQueryHeight::Committed)
+            .await?
+            .0
+            .into_iter()
+            .map(|(key, object)| {
+                let cid = Cid::from(cid::Cid::try_from(object.cid.0)?);
+                Ok((key, (cid, object.size)))
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        let mut local = HashSet::new();
+        let mut entries = Vec::new();
+        for path in files {
+            let key = format!("{}{}", prefix, path_to_key(dir, &path)?);
+            local.insert(key.clone());
+            entries.push((key, path));
+        }
+
+        // Hash and upload concurrently, then broadcast one at a time
+        progress.step(2, 3);
+        let total = entries.len();
+        let upload_signer = signer.clone();
+        let mut uploads = stream::iter(entries)
+            .map(|(key, path)| {
+                let remote = remote.get(&key).copied();
+                let mut signer = upload_signer.clone();
+                async move {
+                    let staged = self
+                        .stage_file(provider, &mut signer, &key, &path, remote)
+                        .await?;
+                    Ok::<_, anyhow::Error>((key, remote.is_some(), staged))
+                }
+            })
+            .buffer_unordered(options.concurrency.max(1));
+
+        let mut summary = SyncSummary::default();
+        let mut synced = 0;
+        while let Some(result) = uploads.next().await {
+            let (key, exists, staged) = result?;
+            synced += 1;
+            progress.status(format!("Synced {}/{} files ({})", synced, total, key));
+            match staged {
+                Staged::Unchanged => summary.unchanged += 1,
+                Staged::Uploaded(params) => {
+                    summary.bytes_transferred += params.size;
+                    let result = self
+                        .broadcast_add(
+                            provider,
+                            signer,
+                            params,
+                            options.broadcast_mode,
+                            options.gas_params.clone(),
+                        )
+                        .await;
+                    if let Err(e) = result {
+                        signer
+                            .sync_sequence(provider)
+                            .await
+                            .context("failed to re-sync sequence after a failed add")?;
+                        return Err(e);
+                    }
+                    if exists {
+                        summary.updated += 1;
+                    } else {
+                        summary.added += 1;
+                    }
+                }
+            }
+        }
+
+        progress.step(3, 3);
+        if options.delete {
+            for key in remote.keys().filter(|k| !local.contains(*k)) {
+                progress.status(format!("Deleting {}...", key));
+                let result = self
+                    .delete(
+                        provider,
+                        signer,
+                        key,
+                        DeleteOptions {
+                            broadcast_mode: options.broadcast_mode,
+                            gas_params: options.gas_params.clone(),
+                            ..Default::default()
+                        },
+                    )
+                    .await;
+                if let Err(e) = result {
+                    signer
+                        .sync_sequence(provider)
+                        .await
+                        .context("failed to re-sync sequence after a failed delete")?;
+                    return Err(e);
+                }
+                summary.deleted += 1;
+            }
+        }
+
+        progress.finished(format!(
+            "Synced {} files in {} (added={}; updated={}; unchanged={}; deleted={})",
+            total,
+            HumanDuration(started.elapsed()),
+            summary.added,
+            summary.updated,
+            summary.unchanged,
+            summary.deleted
+        ));
+        Ok(summary)
+    }
+
+    /// Hash a local file and upload it to the Object API unless the remote object matches.
+    async fn stage_file(
+        &self,
+        provider: &impl ObjectProvider,
+        signer: &mut impl Signer,
+        key: &str,
+        path: &Path,
+        remote: Option<(Cid, usize)>,
+    ) -> anyhow::Result<Staged> {
+        let mut file = File::open(path).await?;
+        let (cid, object_size) = generate_cid(&mut file, &Progress::default()).await?;
+        if remote == Some((cid, object_size)) {
+            return Ok(Staged::Unchanged);
+        }
+
+        file.rewind().await?;
+        let response_cid = self
+            .upload(
+                provider,
+                signer,
+                key,
+                ReaderStream::new(file),
+                cid,
+                object_size,
+                Default::default(),
+                remote.is_some(),
+            )
+            .await?;
+        if response_cid != cid {
+            return Err(anyhow!(
+                "cannot verify object '{}'; cid does not match remote",
+                key
+            ));
+        }
+
+        Ok(Staged::Uploaded(AddParams {
+            key: key.into(),
+            cid: cid.0,
+            overwrite: remote.is_some(),
+            metadata: Default::default(),
+            size: object_size,
+        }))
+    }
+}
+
+/// The outcome of mirroring a single remote object.
+enum Mirrored {
+    /// The local file already matches the remote object.
+    Unchanged,
+    /// The object was downloaded to a new local file.
+    Added(usize),
+    /// The object was downloaded over an existing local file.
+    Updated(usize),
+}
+
+impl ObjectStore {
+    /// Mirror an object store prefix down to a local directory.
+    ///
+    /// The store is walked with the `/` delimiter, and local files whose CID already matches
+    /// the remote object are skipped. Local files that don't exist remotely are left in place.
+    /// Objects that aren't resolved yet or whose keys can't be mapped to a local path, such as
+    /// a key equal to the prefix, are reported in [`SyncSummary::skipped`].
+    pub async fn sync_down(
+        &self,
+        provider: &(impl QueryProvider + ObjectProvider),
+        dir: impl AsRef<Path>,
+        options: SyncDownOptions,
+    ) -> anyhow::Result<SyncSummary> {
+        let started = Instant::now();
+        let progress = &options.progress;
+        let prefix = dir_prefix(&options.prefix);
+
+        progress.step(1, 2);
+        progress.status("Listing remote objects...");
+        let (objects, height) = self.walk_prefix(provider, &prefix, options.height).await?;
+
+        let dir = dir.as_ref();
+        let mut summary = SyncSummary::default();
+        let mut entries = Vec::new();
+        for (key, object) in objects {
+            if !object.resolved {
+                progress.warning(format!("Skipping {}: object is not resolved", key));
+                summary.skipped.push(key);
+                continue;
+            }
+            let path = match key_to_path(&prefix, &key) {
+                Ok(path) => dir.join(path),
+                Err(e) => {
+                    progress.warning(format!("Skipping {}: {}", key, e));
+                    summary.skipped.push(key);
+                    continue;
+                }
+            };
+            let cid = Cid::from(cid::Cid::try_from(object.cid.0)?);
+            entries.push((key, cid, path));
+        }
+
+        progress.step(2, 2);
+        let total = entries.len();
+        let mut downloads = stream::iter(entries)
+            .map(|(key, cid, path)| async move {
+                let mirrored = self
+                    .mirror_object(provider, &key, cid, &path, height)
+                    .await
+                    .with_context(|| format!("failed to sync object '{}'", key))?;
+                Ok::<_, anyhow::Error>((key, mirrored))
+            })
+            .buffer_unordered(options.concurrency.max(1));
+
+        let mut synced = 0;
+        while let Some(result) = downloads.next().await {
+            let (key, mirrored) = result?;
+            synced += 1;
+            progress.status(format!("Synced {}/{} objects ({})", synced, total, key));
+            match mirrored {
+                Mirrored::Unchanged => summary.unchanged += 1,
+                Mirrored::Added(size) => {
+                    summary.added += 1;
+                    summary.bytes_transferred += size;
+                }
+                Mirrored::Updated(size) => {
+                    summary.updated += 1;
+                    summary.bytes_transferred += size;
+                }
+            }
+        }
+
+        progress.finished(format!(
+            "Synced {} objects in {} (added={}; updated={}; unchanged={}; skipped={})",
+            total,
+            HumanDuration(started.elapsed()),
+            summary.added,
+            summary.updated,
+            summary.unchanged,
+            summary.skipped.len()
+        ));
+        Ok(summary)
+    }
+
+    /// Walk every object under a prefix using the `/` delimiter.
+    /// Returns each object's key and state, along with the pinned query height.
+    async fn walk_prefix(
+        &self,
+        provider: &impl QueryProvider,
+        prefix: &str,
+        height: FvmQueryHeight,
+    ) -> anyhow::Result<(Vec<(String, Object)>, u64)> {
+        let mut height = height;
+        let mut objects = Vec::new();
+        let mut pending = vec![prefix.to_string()];
+        while let Some(prefix) = pending.pop() {
+            let options = QueryOptions {
+                prefix,
+                height,
+                ..Default::default()
+            };
+            let mut items = pin!(self.query_stream(provider, options));
+            while let Some(item) = items.next().await {
+                let item = item?;
+                height = FvmQueryHeight::Height(item.height.value());
+                match item.value {
+                    QueryItem::Object(key, object) => objects.push((key, object)),
+                    QueryItem::CommonPrefix(common_prefix) => pending.push(common_prefix),
+                }
+            }
+        }
+        Ok((objects, height.into()))
+    }
+
+    /// Download an object to a local path unless the local file already matches its CID.
+    async fn mirror_object(
+        &self,
+        provider: &(impl QueryProvider + ObjectProvider),
+        key: &str,
+        cid: Cid,
+        path: &Path,
+        height: u64,
+    ) -> anyhow::Result<Mirrored> {
+        let exists = match File::open(path).await {
+            Ok(mut file) => {
+                let (local_cid, _) = generate_cid(&mut file, &Progress::default()).await?;
+                if local_cid == cid {
+                    return Ok(Mirrored::Unchanged);
+                }
+                true
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+            Err(e) => return Err(e.into()),
+        };
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Download next to the destination so an interrupted sync never leaves a partial file
+        let partial = {
+            let mut p = path.to_path_buf().into_os_string();
+            p.push(".adm-partial");
+            PathBuf::from(p)
+        };
+        let file = File::create(&partial).await?;
+        let result = self
+            .get(
+                provider,
+                key,
+                file,
+                GetOptions {
+                    height: FvmQueryHeight::Height(height),
+                    raw: true,
+                    ..Default::default()
+                },
+            )
+            .await;
+        if let Err(e) = result {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(e);
+        }
+        let size = tokio::fs::metadata(&partial).await?.len() as usize;
+        tokio::fs::rename(&partial, path).await?;
+
+        Ok(if exists {
+            Mirrored::Updated(size)
+        } else {
+            Mirrored::Added(size)
+        })
+    }
+}
+
+/// Returns a prefix that maps to a directory, i.e., one that's empty or ends with `/`,
+/// so that "photos" doesn't match keys like "photos-old/cat.jpg".
+fn dir_prefix(prefix: &str) -> String {
+    if prefix.is_empty() || prefix.ends_with('/') {
+        prefix.to_string()
+    } else {
+        format!("{}/", prefix)
+    }
+}
+
+/// Map a key under `prefix` to a relative file path.
+/// The prefix must be empty or end with `/`.
+/// Keys that would escape the destination directory are rejected.
+fn key_to_path(prefix: &str, key: &str) -> anyhow::Result<PathBuf> {
+    let relative = key
+        .strip_prefix(prefix)
+        .ok_or_else(|| anyhow!("key '{}' does not start with prefix '{}'", key, prefix))?;
+    let mut path = PathBuf::new();
+    for part in relative.split('/') {
+        if part.is_empty() || part == "." || part == ".." || part.contains('\\') {
+            return Err(anyhow!("key '{}' cannot be mapped to a local path", key));
+        }
+        path.push(part);
+    }
+    Ok(path)
+}
+
+/// Recursively collect the files in a directory.
+async fn walk_dir(root: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        let mut entries = tokio::fs::read_dir(&dir).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            let file_type = entry.file_type().await?;
+            if file_type.is_dir() {
+                dirs.push(path);
+            } else if file_type.is_file() {
+                files.push(path);
+            } else if file_type.is_symlink() && tokio::fs::metadata(&path).await?.is_file() {
+                // Follow links to files, but not to directories, to avoid cycles
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Map a file path under `root` to a `/`-delimited key.
+fn path_to_key(root: &Path, path: &Path) -> anyhow::Result<String> {
+    let relative = path.strip_prefix(root)?;
+    let parts = relative
+        .components()
+        .map(|c| {
+            c.as_os_str()
+                .to_str()
+                .ok_or_else(|| anyhow!("path '{}' is not valid UTF-8", path.display()))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(parts.join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use fvm_shared::address::Address;
+
+    use super::super::mock::{object, MockQueryProvider};
+    use super::*;
+    use crate::machine::Machine;
+
+    #[test]
+    fn test_key_path_mapping() {
+        let root = Path::new("/tmp/photos");
+        let path = root.join("2024").join("cat.jpg");
+        assert_eq!(path_to_key(root, &path).unwrap(), "2024/cat.jpg");
+
+        assert_eq!(
+            key_to_path("photos/", "photos/2024/cat.jpg").unwrap(),
+            Path::new("2024").join("cat.jpg")
+        );
+        assert!(key_to_path("photos/", "videos/dog.mp4").is_err());
+        assert!(key_to_path("photos/", "photos/../etc/passwd").is_err());
+        assert!(key_to_path("photos/", "photos/2024/").is_err());
+    }
+
+    #[test]
+    fn test_dir_prefix() {
+        assert_eq!(dir_prefix(""), "");
+        assert_eq!(dir_prefix("photos/"), "photos/");
+        assert_eq!(dir_prefix("photos"), "photos/");
+
+        let prefix = dir_prefix("photos");
+        assert_eq!(
+            key_to_path(&prefix, "photos/cat.jpg").unwrap(),
+            Path::new("cat.jpg")
+        );
+        assert!(key_to_path(&prefix, "photos-old/cat.jpg").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_walk_prefix() {
+        let mut provider = MockQueryProvider::new(10);
+        provider.set(
+            1,
+            &[
+                ("photos/2024/cat.jpg", object(b"cat", &[])),
+                ("photos/2024/dog.jpg", object(b"dog", &[])),
+                ("photos/index.html", object(b"index", &[])),
+                ("photos-old/cat.jpg", object(b"old", &[])),
+            ],
+        );
+        provider.set(11, &[]);
+        let store = ObjectStore::attach(Address::new_id(1001));
+
+        let (objects, height) = store
+            .walk_prefix(&provider, "photos/", FvmQueryHeight::Committed)
+            .await
+            .unwrap();
+        let mut keys: Vec<_> = objects.into_iter().map(|(key, _)| key).collect();
+        keys.sort();
+        assert_eq!(
+            keys,
+            [
+                "photos/2024/cat.jpg",
+                "photos/2024/dog.jpg",
+                "photos/index.html"
+            ]
+        );
+        assert_eq!(height, 10);
+        assert!(provider.queries.lock().unwrap().iter().all(|h| *h == 10));
+    }
+}
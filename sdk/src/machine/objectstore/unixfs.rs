@@ -0,0 +1,109 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use unixfs_v1::file::adder::{Chunker, FileAdder};
+
+use adm_provider::response::Cid;
+
+/// Chunk size used to build object CIDs.
+/// This must match the chunker used by the Object API.
+pub(crate) const CHUNK_SIZE: usize = 1024 * 1024; // size-1048576
+
+/// Incrementally builds the UnixFS CID of an object from its streamed bytes.
+pub(crate) struct CidBuilder {
+    adder: FileAdder,
+    last: Option<Cid>,
+    size: usize,
+}
+
+impl Default for CidBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CidBuilder {
+    /// Create a new builder using the Object API chunker.
+    pub fn new() -> Self {
+        let adder = FileAdder::builder()
+            .with_chunker(Chunker::Size(CHUNK_SIZE))
+            .build();
+        Self {
+            adder,
+            last: None,
+            size: 0,
+        }
+    }
+
+    /// Push bytes into the builder.
+    /// Returns the CIDs of leaves that were completed by this push.
+    pub fn push(&mut self, data: &[u8]) -> anyhow::Result<Vec<Cid>> {
+        let leaves = self.push_blocks(data)?;
+        Ok(leaves.into_iter().map(|(c, _)| c).collect())
+    }
+
+    /// Push bytes into the builder.
+    /// Returns the CIDs and encoded blocks of leaves that were completed by this push.
+    pub fn push_blocks(&mut self, mut data: &[u8]) -> anyhow::Result<Vec<(Cid, Vec<u8>)>> {
+        let mut leaves = Vec::new();
+        while !data.is_empty() {
+            let (blocks, n) = self.adder.push(data);
+            for (c, block) in blocks {
+                leaves.push((Cid::from(cid::Cid::try_from(c.to_bytes())?), block));
+            }
+            self.size += n;
+            data = &data[n..];
+        }
+        if let Some((c, _)) = leaves.last() {
+            self.last = Some(*c);
+        }
+        Ok(leaves)
+    }
+
+    /// Returns the number of bytes pushed so far.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Finish the object and return its root CID.
+    pub fn finish(self) -> anyhow::Result<Cid> {
+        let (_, root) = self.finish_blocks()?;
+        Ok(root)
+    }
+
+    /// Finish the object and return its remaining blocks along with its root CID.
+    /// The root block, if not already returned as a leaf, is the last block.
+    pub fn finish_blocks(self) -> anyhow::Result<(Vec<(Cid, Vec<u8>)>, Cid)> {
+        let mut blocks = Vec::new();
+        for (c, block) in self.adder.finish() {
+            blocks.push((Cid::from(cid::Cid::try_from(c.to_bytes())?), block));
+        }
+        let root = match blocks.last() {
+            Some((c, _)) => *c,
+            None => self.last.unwrap_or(Cid::from(cid::Cid::default())),
+        };
+        Ok((blocks, root))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cid_builder_is_independent_of_push_sizes() {
+        let data: Vec<u8> = (0..(CHUNK_SIZE * 2 + 1234)).map(|i| i as u8).collect();
+
+        let mut whole = CidBuilder::new();
+        whole.push(&data).unwrap();
+
+        let mut pieces = CidBuilder::new();
+        for piece in data.chunks(CHUNK_SIZE / 3 + 7) {
+            pieces.push(piece).unwrap();
+        }
+
+        assert_eq!(whole.size(), data.len());
+        assert_eq!(pieces.size(), data.len());
+        assert_eq!(whole.finish().unwrap(), pieces.finish().unwrap());
+    }
+}
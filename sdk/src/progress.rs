@@ -1,54 +1,129 @@
 // Copyright 2024 ADM Contributors
 // SPDX-License-Identifier: Apache-2.0, MIT
 
-use std::fmt::Write;
+use std::fmt;
 use std::sync::Arc;
 use std::time::Duration;
 
-use console::Emoji;
-use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
-use lazy_static::lazy_static;
-
-pub(crate) static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", ":-)");
-
-lazy_static! {
-    static ref SPINNER_STYLE: ProgressStyle =
-        ProgressStyle::with_template("{prefix:.bold.dim} {spinner:.green} {wide_msg}")
-            .unwrap()
-            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]);
-    static ref PROGRESS_STYLE: ProgressStyle = ProgressStyle::with_template(
-        "[{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})"
-    )
-    .unwrap()
-    .with_key("eta", |state: &ProgressState, w: &mut dyn Write| write!(
-        w,
-        "{:.1}s",
-        state.eta().as_secs_f64()
-    )
-    .unwrap())
-    .progress_chars("#>-");
+use tokio::sync::mpsc::UnboundedSender;
+
+use adm_provider::response::Cid;
+
+#[cfg(feature = "indicatif")]
+pub use terminal::TerminalProgress;
+
+#[cfg(feature = "indicatif")]
+mod terminal;
+
+/// An event reported by a long-running operation, like adding or getting an object.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProgressEvent {
+    /// A step of the operation started, e.g., step 2 of 3.
+    Step { step: usize, steps: usize },
+    /// The status of the current step changed.
+    Status(String),
+    /// A chunk of content was hashed while computing an object's CID.
+    Hashed { chunk: Cid },
+    /// An object's content started uploading to the Object API.
+    UploadStarted { cid: Cid, size: usize },
+    /// Bytes of an object's content were uploaded, counted from the start of the object.
+    Uploaded { bytes: usize },
+    /// An object's content finished uploading.
+    UploadFinished,
+    /// A transaction is being broadcast.
+    Broadcasting,
+    /// An object's content started downloading, possibly resuming from an offset.
+    DownloadStarted {
+        cid: Cid,
+        size: usize,
+        offset: usize,
+    },
+    /// Bytes of an object's content were downloaded, counted from the start of the object.
+    Downloaded { bytes: usize },
+    /// An object's content finished downloading.
+    DownloadFinished,
+    /// A problem that doesn't stop the operation.
+    Warning(String),
+    /// The operation finished, with a summary.
+    Finished(String),
 }
 
-/// Create a new progress bar. Use `hide` to hide all child bars.
-pub(crate) fn new_multi_bar(hide: bool) -> Arc<MultiProgress> {
-    if hide {
-        Arc::new(MultiProgress::with_draw_target(ProgressDrawTarget::hidden()))
-    } else {
-        Arc::new(MultiProgress::new())
+/// Observer of the progress of long-running operations.
+///
+/// Events are reported synchronously from the operation's task, so observers should not block.
+pub trait ProgressObserver: Send + Sync {
+    /// Called for each event reported by an operation.
+    fn on_event(&self, event: ProgressEvent);
+}
+
+/// Progress events can be sent to a channel and consumed elsewhere.
+/// Events are dropped once the receiver is closed.
+impl ProgressObserver for UnboundedSender<ProgressEvent> {
+    fn on_event(&self, event: ProgressEvent) {
+        let _ = self.send(event);
     }
 }
 
-/// Create a new progress bar.
-pub(crate) fn new_progress_bar(size: usize) -> ProgressBar {
-    let pb = ProgressBar::new(size as u64);
-    pb.set_style(PROGRESS_STYLE.clone());
-    pb
+/// Reports progress events to an observer, if any.
+///
+/// The default reports nothing.
+#[derive(Clone, Default)]
+pub struct Progress(Option<Arc<dyn ProgressObserver>>);
+
+impl Progress {
+    /// Create a new reporter for an observer.
+    pub fn new(observer: impl ProgressObserver + 'static) -> Self {
+        Self(Some(Arc::new(observer)))
+    }
+
+    /// Returns whether events are reported to an observer.
+    pub fn is_enabled(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Report an event to the observer.
+    pub(crate) fn report(&self, event: ProgressEvent) {
+        if let Some(observer) = &self.0 {
+            observer.on_event(event);
+        }
+    }
+
+    pub(crate) fn step(&self, step: usize, steps: usize) {
+        self.report(ProgressEvent::Step { step, steps });
+    }
+
+    pub(crate) fn status(&self, status: impl Into<String>) {
+        self.report(ProgressEvent::Status(status.into()));
+    }
+
+    pub(crate) fn warning(&self, warning: impl Into<String>) {
+        self.report(ProgressEvent::Warning(warning.into()));
+    }
+
+    pub(crate) fn finished(&self, summary: impl Into<String>) {
+        self.report(ProgressEvent::Finished(summary.into()));
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Progress").field(&self.is_enabled()).finish()
+    }
 }
 
-/// Create a new message bar.
-pub(crate) fn new_message_bar() -> ProgressBar {
-    let pb = ProgressBar::new(0);
-    pb.set_style(SPINNER_STYLE.clone());
-    pb.enable_steady_tick(Duration::from_millis(80));
-    pb
+/// A duration formatted for progress messages in its largest whole unit, e.g., "3 minutes".
+pub(crate) struct HumanDuration(pub Duration);
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let (count, unit) = match secs {
+            s if s >= 86400 => (s / 86400, "day"),
+            s if s >= 3600 => (s / 3600, "hour"),
+            s if s >= 60 => (s / 60, "minute"),
+            s => (s, "second"),
+        };
+        let plural = if count == 1 { "" } else { "s" };
+        write!(f, "{} {}{}", count, unit, plural)
+    }
 }
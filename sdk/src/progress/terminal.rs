@@ -0,0 +1,138 @@
+// Copyright 2024 ADM Contributors
+// SPDX-License-Identifier: Apache-2.0, MIT
+
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use console::Emoji;
+use indicatif::{MultiProgress, ProgressBar, ProgressState, ProgressStyle};
+use lazy_static::lazy_static;
+
+use super::{ProgressEvent, ProgressObserver};
+
+static SPARKLE: Emoji<'_, '_> = Emoji("✨ ", ":-)");
+static WARNING: Emoji<'_, '_> = Emoji("⚠️ ", ":-(");
+
+lazy_static! {
+    static ref SPINNER_STYLE: ProgressStyle =
+        ProgressStyle::with_template("{prefix:.bold.dim} {spinner:.green} {wide_msg}")
+            .unwrap()
+            .tick_strings(&["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"]);
+    static ref PROGRESS_STYLE: ProgressStyle = ProgressStyle::with_template(
+        "[{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})"
+    )
+    .unwrap()
+    .with_key("eta", |state: &ProgressState, w: &mut dyn Write| write!(
+        w,
+        "{:.1}s",
+        state.eta().as_secs_f64()
+    )
+    .unwrap())
+    .progress_chars("#>-");
+}
+
+/// Progress observer that draws indicatif bars on the terminal (useful for command-line
+/// interfaces).
+///
+/// A spinner shows the current step and status, and a progress bar tracks uploaded or
+/// downloaded bytes. Warnings and summaries are printed above the bars.
+#[derive(Default)]
+pub struct TerminalProgress {
+    bars: MultiProgress,
+    state: Mutex<Bars>,
+}
+
+/// Bars of the operation in progress.
+#[derive(Default)]
+struct Bars {
+    message: Option<ProgressBar>,
+    transfer: Option<ProgressBar>,
+}
+
+impl TerminalProgress {
+    /// Create a new terminal progress observer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl ProgressObserver for TerminalProgress {
+    fn on_event(&self, event: ProgressEvent) {
+        let mut state = self.state.lock().unwrap();
+        let message = state
+            .message
+            .get_or_insert_with(|| self.bars.add(new_message_bar()))
+            .clone();
+        match event {
+            ProgressEvent::Step { step, steps } => {
+                message.set_prefix(format!("[{}/{}]", step, steps));
+            }
+            ProgressEvent::Status(status) => message.set_message(status),
+            ProgressEvent::Hashed { chunk } => {
+                message.set_message(format!("Processed chunk: {}", chunk));
+            }
+            ProgressEvent::UploadStarted { cid, size } => {
+                message.set_message(format!("Uploading {} to network...", cid));
+                state.transfer = Some(self.bars.add(new_progress_bar(size)));
+            }
+            ProgressEvent::DownloadStarted { cid, size, offset } => {
+                message.set_message(format!("Downloading {}... ", cid));
+                let transfer = self.bars.add(new_progress_bar(size));
+                transfer.set_position(offset as u64);
+                state.transfer = Some(transfer);
+            }
+            ProgressEvent::Uploaded { bytes } | ProgressEvent::Downloaded { bytes } => {
+                if let Some(transfer) = &state.transfer {
+                    transfer.set_position(bytes as u64);
+                }
+            }
+            ProgressEvent::UploadFinished | ProgressEvent::DownloadFinished => {
+                if let Some(transfer) = state.transfer.take() {
+                    transfer.finish_and_clear();
+                }
+            }
+            ProgressEvent::Broadcasting => message.set_message("Broadcasting transaction..."),
+            ProgressEvent::Warning(warning) => {
+                message.println(format!("{} {}", WARNING, warning));
+            }
+            ProgressEvent::Finished(summary) => {
+                message.println(format!("{} {}", SPARKLE, summary));
+                message.finish_and_clear();
+                if let Some(transfer) = state.transfer.take() {
+                    transfer.finish_and_clear();
+                }
+                state.message = None;
+            }
+        }
+    }
+}
+
+impl Drop for TerminalProgress {
+    fn drop(&mut self) {
+        // Clear the bars of an operation that ended without a summary, e.g., with an error
+        if let Ok(state) = self.state.get_mut() {
+            for bar in [state.message.take(), state.transfer.take()]
+                .into_iter()
+                .flatten()
+            {
+                bar.finish_and_clear();
+            }
+        }
+    }
+}
+
+/// Create a new progress bar.
+fn new_progress_bar(size: usize) -> ProgressBar {
+    let pb = ProgressBar::new(size as u64);
+    pb.set_style(PROGRESS_STYLE.clone());
+    pb
+}
+
+/// Create a new message bar.
+fn new_message_bar() -> ProgressBar {
+    let pb = ProgressBar::new(0);
+    pb.set_style(SPINNER_STYLE.clone());
+    pb.enable_steady_tick(Duration::from_millis(80));
+    pb
+}
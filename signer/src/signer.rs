@@ -11,6 +11,7 @@ use fvm_shared::{
 };
 
 use adm_provider::message::GasParams;
+use adm_provider::query::QueryProvider;
 use adm_provider::util::get_delegated_address;
 
 use crate::SubnetID;
@@ -48,6 +49,18 @@ pub trait Signer: Clone + Send + Sync {
         gas_params: GasParams,
     ) -> anyhow::Result<ChainMessage>;
 
+    /// Re-syncs the signer's sequence (nonce) with the chain's pending state.
+    ///
+    /// A transaction that's rejected before it's included doesn't consume its sequence, so
+    /// signers that track the sequence locally should be re-synced after a failed broadcast.
+    /// Signers that don't track a sequence can rely on the default, which does nothing.
+    async fn sync_sequence<P>(&mut self, _provider: &P) -> anyhow::Result<()>
+    where
+        P: QueryProvider,
+    {
+        Ok(())
+    }
+
     /// Returns a raw [`SignedMessage`].  
     fn sign_message(
         &self,
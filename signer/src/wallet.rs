@@ -85,6 +85,13 @@ impl Signer for Wallet {
         Ok(ChainMessage::Signed(signed))
     }
 
+    async fn sync_sequence<P>(&mut self, provider: &P) -> anyhow::Result<()>
+    where
+        P: QueryProvider,
+    {
+        self.init_sequence(provider).await
+    }
+
     fn sign_message(
         &self,
         message: Message,
@@ -217,4 +224,31 @@ mod tests {
         wallet.set_sequence(None, &mock_provider).await.unwrap();
         assert_eq!(*wallet.sequence.lock().await, 65);
     }
+
+    #[tokio::test]
+    async fn test_sync_sequence() {
+        let mock_provider = MockQueryProvider;
+        let private_key = crate::key::random_secretkey();
+        let subnet_id = SubnetID::from_str("r/foobar").unwrap();
+        let mut wallet =
+            Wallet::new_secp256k1(private_key.clone(), AccountKind::Ethereum, subnet_id).unwrap();
+        wallet.set_sequence(Some(65), &mock_provider).await.unwrap();
+
+        // A signed transaction advances the local sequence even if it's never included
+        wallet
+            .transaction(
+                Address::new_id(1001),
+                Default::default(),
+                0,
+                RawBytes::default(),
+                None,
+                Default::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(*wallet.sequence.lock().await, 66);
+
+        wallet.sync_sequence(&mock_provider).await.unwrap();
+        assert_eq!(*wallet.sequence.lock().await, 65);
+    }
 }